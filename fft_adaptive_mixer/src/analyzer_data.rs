@@ -1,4 +1,5 @@
 use nih_plug::prelude::Enum;
+use spectral_core::analyzer::AnalyzerSpectra;
 
 #[derive(Enum, Debug, Clone, Copy, PartialEq)]
 pub enum AnalyzerChannel {
@@ -9,8 +10,8 @@ pub enum AnalyzerChannel {
 
 #[derive(Debug, Clone)]
 pub struct AnalyzerData {
-    pub num_bins: usize,
-    pub magnitudes: Vec<f32>,
+    // what the shared analyzer draws, the rest is this plugin's own curves
+    pub spectra: AnalyzerSpectra,
     pub frequencies: Vec<f32>,
     pub reduction: Vec<f32>,
     pub lowcut: f32,
@@ -22,8 +23,7 @@ pub struct AnalyzerData {
 impl AnalyzerData {
     pub fn new(num_bins: usize, sr: usize) -> Self {
         Self {
-            num_bins,
-            magnitudes: vec![0.0f32; num_bins],
+            spectra: AnalyzerSpectra::new(num_bins),
             frequencies: vec![0.0f32; num_bins],
             reduction: vec![0.0f32; num_bins],
             lowcut: 50.0f32,
@@ -1,6 +1,5 @@
 use std::sync::{Arc, Mutex};
 
-use analyzer::Analyzer;
 use nih_plug::prelude::{util, AtomicF32, Editor, Vst3Plugin};
 use nih_plug_vizia::vizia::image::{Pixel, Pixels};
 use nih_plug_vizia::vizia::style::Color;
@@ -8,6 +7,7 @@ use nih_plug_vizia::vizia::vg::Canvas;
 use nih_plug_vizia::vizia::{prelude::*, vg};
 use nih_plug_vizia::widgets::*;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
+use spectral_core::editor::{analyzer::Analyzer, AnalyzerParams};
 use param_knob::ParamKnob;
 use peak_curve::PeakCurve;
 
@@ -41,6 +41,12 @@ pub struct EditorData {
 
 impl Model for EditorData {}
 
+impl AnalyzerParams for PluginParams {
+    fn analyzer_axes(&self) -> &Mutex<[f32; 4]> {
+        &self.analyzer_axes
+    }
+}
+
 pub(crate) fn create(
     editor_state: Arc<ViziaState>,
     editor_data: EditorData,
@@ -171,6 +177,45 @@ pub(crate) fn create(
                     .child_left(Stretch(1.0))
                     .child_right(Stretch(1.0))
                     .bottom(Pixels(10.0));
+
+                    VStack::new(cx, |cx| {
+                        ParamSlider::new(cx, EditorData::plugin_data, |params| {
+                            &params.analyzer_mode
+                        })
+                            .font_family(vec![FamilyOwned::Name(String::from(COMFORTAA))])
+                            .font_size(13.0)
+                            .top(Pixels(8.0))
+                            .height(Pixels(20.0))
+                            .width(Pixels(100.0));
+                        Label::new(cx, "Analyzer Mode:")
+                            .font_family(vec![FamilyOwned::Name(String::from(COMFORTAA))])
+                            .font_size(13.0)
+                            .height(Pixels(22.0))
+                            .top(Pixels(2.0))
+                            .space(Stretch(1.0));
+                    })
+                    .row_between(Pixels(0.0))
+                    .height(Pixels(42.0))
+                    .child_left(Stretch(1.0))
+                    .child_right(Stretch(1.0))
+                    .bottom(Pixels(10.0));
+
+                    VStack::new(cx, |cx| {
+                        ParamButton::new(cx, EditorData::plugin_data, |params| &params.analyzer_show_input)
+                            .with_label("Input")
+                            .font_size(13.0);
+                        ParamButton::new(cx, EditorData::plugin_data, |params| &params.analyzer_show_output)
+                            .with_label("Output")
+                            .font_size(13.0);
+                        ParamButton::new(cx, EditorData::plugin_data, |params| &params.analyzer_show_aux)
+                            .with_label("Sidechain")
+                            .font_size(13.0);
+                    })
+                    .row_between(Pixels(5.0))
+                    .height(Pixels(90.0))
+                    .child_left(Stretch(1.0))
+                    .child_right(Stretch(1.0))
+                    .bottom(Pixels(10.0));
                 })
                 .row_between(Pixels(0.0))
                 .child_left(Stretch(1.0))
@@ -181,7 +226,7 @@ pub(crate) fn create(
 
                 // ANALYZER + EQ
                 VStack::new(cx, |cx| {
-                    Analyzer::new(cx, EditorData::analyzer_data, EditorData::sample_rate)
+                    Analyzer::new(cx, EditorData::plugin_data, EditorData::analyzer_data, EditorData::sample_rate)
                         .left(Stretch(1.0))
                         .right(Stretch(1.0))
                         .width(Pixels(850.0))
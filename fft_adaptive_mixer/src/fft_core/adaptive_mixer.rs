@@ -1,6 +1,8 @@
 use realfft::{num_complex::Complex, num_traits::Zero};
 
-use crate::utils::{self, SimpleLPF};
+use spectral_core::{analyzer::AnalyzerLayers, fft_data::{self, FFTData}, fft_size::FFTSize, resolution::{BandSize, MAX_BANDS, MAX_EFFECT_BANDS}, smoothing::HopSmoother, spectral_effect::{SpectralEffect, SpectrumValues}, utils::{self, SimpleLPF}};
+
+use crate::analyzer_data::{AnalyzerChannel, AnalyzerData};
 use nih_plug::nih_log;
 
 const EQ_FREQS: [f32; 9] = [20.0, 189.32, 368.40, 716.87, 1394.95, 2714.41, 5281.95, 10278.08, 20000.0]; 
@@ -18,44 +20,64 @@ pub struct AdaptiveMixer {
     pub peakiness: f32,
     pub eq: Vec<f32>,
 
-    pub mags_eq: [f32; 4096],
-    pub db_eq: [f32; 4096],
+    pub mags_eq: Vec<f32>,
+    pub db_eq: Vec<f32>,
 
     pub fft_size: usize,
     pub sample_rate: f32,
 
-    pub time_lpf: [SimpleLPF; 4096],
+    // one smoother per bin for each band of the processor's `Resolution` and of the outgoing engine
+    // while it crossfades, the bands have their own bin spacings
+    pub time_lpf: Vec<Vec<SimpleLPF>>,
+    time: f32,
+
+    // the gain, cutoffs, gate and EQ above are the smoothed values of the current hop
+    reduction_smoother: HopSmoother,
+    lowcut_smoother: HopSmoother,
+    highcut_smoother: HopSmoother,
+    gate_smoother: HopSmoother,
+    eq_smoothers: [HopSmoother; 8],
+    band: usize,
+
+    analyzer_input_data: triple_buffer::Input<AnalyzerData>,
+    analyzer_channel: AnalyzerChannel,
+    pub analyzer_layers: AnalyzerLayers,
 }
 
 impl AdaptiveMixer {
-    pub fn new(num_bins: usize, sr: f32) -> Self {
+    pub fn new(num_bins: usize, sr: f32, analyzer_buffer: triple_buffer::Input<AnalyzerData>) -> Self {
         Self {
             reduction_amount: 0.0,
             lowcut: 20.0,
             highcut: 20_000.0,
-            exp_mags: vec![0.0f32; num_bins],
-            reduction: vec![0.0f32; num_bins],
-            peaked: vec![0.0f32; num_bins],
+            exp_mags: utils::bins_vec(num_bins, 0.0f32),
+            reduction: utils::bins_vec(num_bins, 0.0f32),
+            peaked: utils::bins_vec(num_bins, 0.0f32),
             gate: -120.0,
             lpf: SimpleLPF::new(0.001f32),
             smoothness: 0.0f32,
             peakiness: 1.0f32,
             eq: vec![0.0f32; 8],
 
-            mags_eq: [0.0f32; 4096],
-            db_eq: [0.0f32; 4096],
+            mags_eq: vec![0.0f32; FFTSize::MAX.num_bins()],
+            db_eq: vec![0.0f32; FFTSize::MAX.num_bins()],
 
             fft_size: num_bins * 2,
             sample_rate: sr,
-            time_lpf: [SimpleLPF::new(0.0); 4096],
-        }
-    }
+            time_lpf: (0..MAX_EFFECT_BANDS).map(|_| utils::bins_vec(num_bins, SimpleLPF::new(0.0))).collect(),
+            time: 0.0,
 
-    pub fn resize(&mut self, new_bin_size: usize) {
-        self.fft_size = new_bin_size * 2;
-        self.reduction.resize(new_bin_size, 0.0f32);
-        self.exp_mags.resize(new_bin_size, 0.0f32);
-        self.peaked.resize(new_bin_size, 0.0f32);
+            reduction_smoother: HopSmoother::new(0.0),
+            lowcut_smoother: HopSmoother::new(20.0),
+            highcut_smoother: HopSmoother::new(20_000.0),
+            gate_smoother: HopSmoother::new(-120.0),
+            eq_smoothers: [0.0; 8].map(HopSmoother::new),
+            band: 0,
+
+            analyzer_input_data: analyzer_buffer,
+            analyzer_channel: AnalyzerChannel::Merged,
+            analyzer_layers: AnalyzerLayers::new(),
+        }
     }
 
     pub fn set_params(&mut self, 
@@ -74,30 +96,39 @@ impl AdaptiveMixer {
         eq6: f32,
         eq7: f32,
         eq8: f32,
+        an_chan: AnalyzerChannel,
     ) {
-        self.reduction_amount = side_gain;
-        self.lowcut = low;
-        self.highcut = high;
-        self.gate = gate;
+        self.analyzer_channel = an_chan;
+        self.reduction_smoother.set_target(side_gain);
+        self.lowcut_smoother.set_target(low);
+        self.highcut_smoother.set_target(high);
+        self.gate_smoother.set_target(gate);
         self.smoothness = smooth;
         self.peakiness = peakiness;
         self.lpf.set_a(smooth);
-        if self.time_lpf[0].a != time {
-            for time_smooth in self.time_lpf.iter_mut() {
+        if self.time != time {
+            self.time = time;
+            for time_smooth in self.time_lpf.iter_mut().flatten() {
                 time_smooth.set_a(time);
             }
         }
-        self.eq[0] = eq1;
-        self.eq[1] = eq2;
-        self.eq[2] = eq3;
-        self.eq[3] = eq4;
-        self.eq[4] = eq5;
-        self.eq[5] = eq6;
-        self.eq[6] = eq7;
-        self.eq[7] = eq8;
+        for (smoother, eq) in self.eq_smoothers.iter_mut().zip([eq1, eq2, eq3, eq4, eq5, eq6, eq7, eq8]) {
+            smoother.set_target(eq);
+        }
     }   
 
-    pub fn get_max_within_cutoffs(&self, arr: &Vec<f32>, freq: &Vec<f32>) -> f32 {
+    /// Moves the smoothed parameters one hop further towards their targets.
+    fn next_hop(&mut self) {
+        self.reduction_amount = self.reduction_smoother.next_hop();
+        self.lowcut = self.lowcut_smoother.next_hop();
+        self.highcut = self.highcut_smoother.next_hop();
+        self.gate = self.gate_smoother.next_hop();
+        for (eq, smoother) in self.eq.iter_mut().zip(self.eq_smoothers.iter_mut()) {
+            *eq = smoother.next_hop();
+        }
+    }
+
+    pub fn get_max_within_cutoffs(&self, arr: &[f32], freq: &[f32]) -> f32 {
         let mut max = std::f32::MIN;
 
         for (el, f) in arr.iter().zip(freq.iter()) {
@@ -110,60 +141,138 @@ impl AdaptiveMixer {
         }
         max
     }
+}
 
-    pub fn process_spectrum(&mut self, 
-        mag: [&Vec<f32>; 2], 
-        phase: [&Vec<f32>; 2], 
-        db: [&Vec<f32>; 2], 
-        freq: [&Vec<f32>; 2], 
-        aux_db: [&Vec<f32>; 2],
-        aux_mag: [&Vec<f32>; 2],
-        output_buffer: &mut [Vec<Complex<f32>>; 2]) 
-    {
+impl SpectralEffect for AdaptiveMixer {
+    fn resize(&mut self, fft_size: usize) {
+        let new_bin_size = utils::fft_size_to_bins(fft_size);
+        self.fft_size = fft_size;
+        self.reduction.resize(new_bin_size, 0.0f32);
+        self.exp_mags.resize(new_bin_size, 0.0f32);
+        self.peaked.resize(new_bin_size, 0.0f32);
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.analyzer_layers.set_hop_rate(sample_rate, hop_size);
+        self.sample_rate = sample_rate as f32;
+        let smoothers = [&mut self.reduction_smoother, &mut self.lowcut_smoother, &mut self.highcut_smoother, &mut self.gate_smoother];
+        for smoother in smoothers.into_iter().chain(self.eq_smoothers.iter_mut()) {
+            smoother.set_hop_rate(sample_rate, hop_size);
+        }
+    }
+
+    // the smoothers of each band are resized to its number of bins, within their capacity
+    fn set_bands(&mut self, bands: &[BandSize]) {
+        for (time_lpf, band) in self.time_lpf.iter_mut().zip(bands) {
+            time_lpf.resize(utils::fft_size_to_bins(band.transform_size), SimpleLPF::new(self.time));
+        }
+    }
+
+    fn set_band(&mut self, band: usize) {
+        self.band = band;
+    }
+
+    // the outgoing engine keeps smoothing where it was, the incoming one starts over
+    fn crossfade_started(&mut self) {
+        for band in 0..MAX_BANDS {
+            self.time_lpf.swap(band, MAX_BANDS + band);
+        }
+        for time_smooth in self.time_lpf[..MAX_BANDS].iter_mut().flatten() {
+            time_smooth.reset();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.analyzer_layers.reset();
+        self.reduction.fill(0.0);
+        self.exp_mags.fill(0.0);
+        self.peaked.fill(0.0);
+        self.lpf.reset();
+        for time_smooth in self.time_lpf.iter_mut().flatten() {
+            time_smooth.reset();
+        }
+        let smoothers = [&mut self.reduction_smoother, &mut self.lowcut_smoother, &mut self.highcut_smoother, &mut self.gate_smoother];
+        for smoother in smoothers.into_iter().chain(self.eq_smoothers.iter_mut()) {
+            smoother.reset();
+        }
+    }
+
+    // the sidechain is analyzed in dB, the reduction is applied to the complex bins
+    fn spectrum_values(&self) -> SpectrumValues {
+        SpectrumValues {
+            magnitude: true,
+            db: true,
+            ..SpectrumValues::NONE
+        }
+    }
+
+    fn process_spectrum(
+        &mut self,
+        input: &[FFTData],
+        aux: Option<&[FFTData]>,
+        output_buffer: &mut [Vec<Complex<f32>>],
+    ) {
+        let Some(aux) = aux else {
+            return;
+        };
+        // the parameters move once per hop of the main band
+        if self.band == 0 {
+            self.next_hop();
+        }
         let one_over_p = 1.0f32 / self.peakiness;
-        for channel in 0..2 {
+        for channel in 0..input.len() {
+            let bins = &input[channel].fft_out;
+            let freq = &input[channel].spectrum_freq;
+            let aux_db = &aux[channel].spectrum_db;
+            // the bands of a multi-resolution processor have their own transform sizes, so the EQ
+            // bands are mapped to bins from the spectrum at hand rather than the largest size
+            let fft_size = (bins.len() - 1) * 2;
+            let to_bin = |freq: f32| utils::freq_to_bin(freq, fft_size, self.sample_rate).min(bins.len() - 1);
+            // the buffers are sized for the largest band, only this spectrum's bins are current
+            let len = bins.len();
+
             // FIRST EQ THE AUX SIGNAL WITH OUT SIMPLE 8-BAND STEP EQ
-            //nih_log!("db[10] at entry {}", aux_db[channel][10]);
+            //nih_log!("db[10] at entry {}", aux_db[10]);
             for (i, eq) in self.eq.iter().enumerate() {
-                let bin_min = utils::freq_to_bin(EQ_FREQS[i], self.fft_size, self.sample_rate);
-                let bin_max = utils::freq_to_bin(EQ_FREQS[i+1], self.fft_size, self.sample_rate);
+                let bin_min = to_bin(EQ_FREQS[i]);
+                let bin_max = to_bin(EQ_FREQS[i+1]);
                 for o in bin_min..bin_max {
-                    //self.mags_eq[o] = aux_mag[channel][o] * *eq;
+                    //self.mags_eq[o] = aux_mag[o] * *eq;
                     // if o == 50  {
-                    //     nih_log!("db {} + eq {} = {}", aux_db[channel][o], *eq, aux_db[channel][o] + *eq);
+                    //     nih_log!("db {} + eq {} = {}", aux_db[o], *eq, aux_db[o] + *eq);
                     // }
-                    self.db_eq[o] = aux_db[channel][o] + *eq;
+                    self.db_eq[o] = aux_db[o] + *eq;
                 }
             }
             // Fill the rest of the buffer with bands eq'd with the last high shelf
-            for i in utils::freq_to_bin(EQ_FREQS[8], self.fft_size, self.sample_rate)..(aux_mag[channel].len() - 1) {
-                //self.mags_eq[i] = aux_mag[channel][i] * self.eq[7];
-                self.db_eq[i] = aux_db[channel][i] + self.eq[7];
+            for i in to_bin(EQ_FREQS[8])..len {
+                //self.mags_eq[i] = aux_mag[i] * self.eq[7];
+                self.db_eq[i] = aux_db[i] + self.eq[7];
             }
 
             // rescale into 0-1
-            for (i, db) in self.peaked.iter_mut().enumerate() {
+            for (i, db) in self.peaked[..len].iter_mut().enumerate() {
                 *db = self.db_eq[i] / 120.0 + 1.0
             }
             //nih_log!("db[100] after 0-1 scaling {}", self.peaked[100]);
-            let max = self.get_max_within_cutoffs(&self.peaked, &freq[channel]).max(-90.0);
+            let max = self.get_max_within_cutoffs(&self.peaked[..len], freq).max(-90.0);
             // normalize the 0-1 so that the highest peak is equal to 1.0
-            for (i, db) in self.peaked.iter_mut().enumerate() {
+            for db in self.peaked[..len].iter_mut() {
                 *db = *db / max;
             }
 
             // rescale back to db values -> highest peak is now 0.0, lowest possible is -100
-            for (i, db) in self.peaked.iter_mut().enumerate() {
+            for db in self.peaked[..len].iter_mut() {
                 *db = *db * 100.0 - 100.0;
             }
 
             // calculate diff between nonpeaked and peaked
             // smoothed now becomes dB DIFFERENCE of peaked and non-peaked
-            for (i, peaked) in self.peaked.iter_mut().enumerate() {
+            for peaked in self.peaked[..len].iter_mut() {
                 *peaked = utils::peakiness_scaled(*peaked, self.peakiness, one_over_p, -100.0, 100.0, -100.0, 100.0) - *peaked;
             }
             
-            for (i, peaked) in self.peaked.iter_mut().enumerate() {
+            for (i, peaked) in self.peaked[..len].iter_mut().enumerate() {
                 *peaked = self.db_eq[i] + *peaked;
                 if *peaked < utils::gain_to_db(self.gate) {
                     *peaked = -120.0; // mute if below gate
@@ -177,26 +286,79 @@ impl AdaptiveMixer {
                 };
             }
 
-            let len = self.reduction.len();
-            for red in self.reduction.iter_mut().skip(1).take(len - 2) {
+            for red in self.reduction[..len].iter_mut().skip(1).take(len - 2) {
                 *red = self.lpf.process(*red);
             }
-            for red in self.reduction.iter_mut().rev().skip(1).take(len - 2) {
+            for red in self.reduction[..len].iter_mut().rev().skip(1).take(len - 2) {
                 *red = self.lpf.process(*red);
             }
 
-            for (i, db) in db[channel].iter().enumerate() {
-                if freq[channel][i] < self.lowcut || freq[channel][i] > self.highcut {
-                    output_buffer[channel][i] = Complex::from_polar(utils::db_to_gain(*db), phase[channel][i]);
-                    self.reduction[i] = self.time_lpf[i].process(0.0f32);
+            let time_lpf = &mut self.time_lpf[self.band];
+            for i in 0..len {
+                if freq[i] < self.lowcut || freq[i] > self.highcut {
+                    output_buffer[channel][i] = bins[i];
+                    self.reduction[i] = time_lpf[i].process(0.0f32);
                     continue;
                 } 
-                self.reduction[i] = self.time_lpf[i].process(self.reduction[i]);
-                output_buffer[channel][i] = Complex::from_polar(utils::db_to_gain(db - self.reduction[i]), phase[channel][i]);
+                self.reduction[i] = time_lpf[i].process(self.reduction[i]);
+                output_buffer[channel][i] = bins[i] * utils::db_to_gain(-self.reduction[i]);
             }
             output_buffer[channel][0] = Complex::zero();
-            output_buffer[channel][aux_db[0].len() - 1] = Complex::zero();
+            output_buffer[channel][aux_db.len() - 1] = Complex::zero();
+        }
+    }
+
+    fn analyze(&mut self, output: &[FFTData], aux: Option<&[FFTData]>, fft_size: usize) {
+        let num_bins = utils::fft_size_to_bins(fft_size);
+        let analyzer_input = self.analyzer_input_data.input_buffer();
+        analyzer_input.spectra.magnitudes.fill(0.0f32);
+        analyzer_input.reduction.fill(0.0f32);
+        analyzer_input.spectra.num_bins = num_bins;
+        analyzer_input.p = self.peakiness;
+
+        for (i, eq) in self.eq.iter().enumerate() {
+            analyzer_input.eq[i] = *eq;
+        }
+
+        // mono only has a left channel
+        let right = output.get(1).unwrap_or(&output[0]);
+        for (i, mag) in analyzer_input.spectra.magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].spectrum_db[i],
+                AnalyzerChannel::Right => right.spectrum_db[i],
+                AnalyzerChannel::Merged => fft_data::average_db(output, i),
+            };
+        }
+        for (i, mag) in analyzer_input.spectra.input_magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].analyzer_input_db[i],
+                AnalyzerChannel::Right => right.analyzer_input_db[i],
+                AnalyzerChannel::Merged => fft_data::average_input_db(output, i),
+            };
+        }
+        if let Some(aux) = aux {
+            let aux_right = aux.get(1).unwrap_or(&aux[0]);
+            for (i, mag) in analyzer_input.spectra.aux_magnitudes[0..num_bins].iter_mut().enumerate() {
+                *mag = match self.analyzer_channel {
+                    AnalyzerChannel::Left => aux[0].analyzer_input_db[i],
+                    AnalyzerChannel::Right => aux_right.analyzer_input_db[i],
+                    AnalyzerChannel::Merged => fft_data::average_input_db(aux, i),
+                };
+            }
+        }
+
+        for (i, f) in analyzer_input.frequencies[0..num_bins].iter_mut().enumerate() {
+            *f = output[0].spectrum_freq[i];
+        }
+
+        analyzer_input.lowcut = self.lowcut;
+        analyzer_input.highcut = self.highcut;
+
+        for (i, reduction) in analyzer_input.reduction[0..num_bins].iter_mut().enumerate() {
+            *reduction = self.reduction[i];
         }
+        self.analyzer_layers.process_spectra(&mut analyzer_input.spectra, aux.is_some());
+        self.analyzer_input_data.publish();
     }
 }
 
@@ -1,8 +1,9 @@
 use analyzer_data::{AnalyzerChannel, AnalyzerData};
-use fft_core::{fft_size::FFTSize, stereo_fft_processor::StereoFFTProcessor};
+use fft_core::adaptive_mixer::AdaptiveMixer;
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
 use params::PluginParams;
+use spectral_core::{fft_processor::FFTProcessor, fft_size::FFTSize, layouts, overlap::Overlap};
 use std::{
     env,
     f32::consts::PI,
@@ -17,7 +18,6 @@ use util::db_to_gain;
 mod analyzer_data;
 mod editor;
 mod fft_core;
-mod utils;
 mod params;
 
 // const FFT_SIZE: usize = 1024;
@@ -25,36 +25,39 @@ mod params;
 // const NUM_BINS: usize = FFT_SIZE / 2 + 1;
 // const OVERLAP: usize = 4;
 // const HOP_SIZE: usize = FFT_SIZE / OVERLAP;
-const WINDOW_CORRECTION: f32 = 2.0 / 3.0;
 
 pub struct PluginData {
-    stereo_fft_processor: StereoFFTProcessor,
+    fft_processor: FFTProcessor<AdaptiveMixer>,
     params: Arc<PluginParams>,
     analyzer_output_data: Arc<Mutex<triple_buffer::Output<AnalyzerData>>>,
     sample_rate: Arc<AtomicF32>,
     size_changed: Arc<AtomicBool>,
+    // a mono input on a stereo output only fills the first channel
+    main_input_channels: usize,
 }
 
 impl Default for PluginData {
     fn default() -> Self {
         let (analyzer_input_data, analyzer_output_data) = TripleBuffer::new(&AnalyzerData::new(
-            utils::fft_size_to_bins(FFTSize::_4096 as usize),
+            FFTSize::MAX.num_bins(),
             44100,
         ))
         .split();
         let size_changed = Arc::new(AtomicBool::new(false));
 
         Self {
-            stereo_fft_processor: StereoFFTProcessor::new(
+            fft_processor: FFTProcessor::new_with_aux(
                 44100,
+                2,
                 FFTSize::_1024 as usize,
-                size_changed.clone(),
-                analyzer_input_data,
+                Overlap::_4 as usize,
+                AdaptiveMixer::new(FFTSize::_1024.num_bins(), 44100.0, analyzer_input_data),
             ),
             params: Arc::new(PluginParams::new(size_changed.clone())),
             analyzer_output_data: Arc::new(Mutex::new(analyzer_output_data)),
-            sample_rate: Arc::new(AtomicF32::new(1.0)),
+            sample_rate: Arc::new(AtomicF32::new(44100.0)),
             size_changed: size_changed.clone(),
+            main_input_channels: 2,
         }
     }
 }
@@ -69,14 +72,7 @@ impl Plugin for PluginData {
 
     // The first audio IO layout is used as the default. The other layouts may be selected either
     // explicitly or automatically by the host or the user depending on the plugin API/backend.
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
-
-        aux_input_ports: &[new_nonzero_u32(2)],
-
-        ..AudioIOLayout::const_default()
-    }];
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = layouts::SIDECHAIN_LAYOUTS;
 
     const MIDI_INPUT: MidiConfig = MidiConfig::None;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
@@ -108,8 +104,13 @@ impl Plugin for PluginData {
         // function if you do not need it.
         let new_size = self.params.fft_size.value();
         _context.set_latency_samples(new_size as u32);
-        self.stereo_fft_processor.change_fft_size(new_size as usize);
-        self.stereo_fft_processor
+        let (num_channels, input_channels) = layouts::channel_counts(_audio_io_layout);
+        self.main_input_channels = input_channels;
+        self.fft_processor.set_num_channels(num_channels);
+        self.fft_processor.change_overlap(self.params.overlap.value() as usize);
+        self.fft_processor.change_zero_padding(self.params.zero_padding.value() as usize);
+        self.fft_processor.change_fft_size(new_size as usize);
+        self.fft_processor
             .set_sample_rate(_buffer_config.sample_rate as usize);
         self.sample_rate.store(
             _buffer_config.sample_rate,
@@ -122,7 +123,10 @@ impl Plugin for PluginData {
         // Reset buffers and envelopes here. This can be called from the audio thread and may not
         // allocate. You can remove this function if you do not need it.
         let new_size = self.params.fft_size.value();
-        self.stereo_fft_processor.change_fft_size(new_size as usize);
+        self.fft_processor.change_overlap(self.params.overlap.value() as usize);
+        self.fft_processor.change_zero_padding(self.params.zero_padding.value() as usize);
+        self.fft_processor.change_fft_size(new_size as usize);
+        self.fft_processor.reset();
     }
 
     fn process(
@@ -151,12 +155,22 @@ impl Plugin for PluginData {
         let eq8 = self.params.eq8.value();
 
         if self.size_changed.load(Ordering::Relaxed) {
-            _context.set_latency_samples(fft_size as u32);
-            self.stereo_fft_processor.change_fft_size(fft_size as usize);
+            self.fft_processor.crossfade_to(
+                fft_size as usize,
+                self.params.overlap.value() as usize,
+                self.params.zero_padding.value() as usize,
+            );
+            _context.set_latency_samples(self.fft_processor.latency_samples() as u32);
             self.size_changed.store(false, Ordering::Relaxed);
         }
 
-        self.stereo_fft_processor.set_params(
+        self.fft_processor.set_window(self.params.window_type.value(), self.params.kaiser_beta.value());
+        self.fft_processor.set_mix(self.params.mix.value());
+        self.fft_processor.set_bypass(self.params.bypass.value());
+        // nobody sees the analyzer while the editor is closed
+        self.fft_processor.set_analyzer_active(self.params.editor_state.is_open());
+
+        self.fft_processor.fft_effect.set_params(
                 side_gain,
                 lowcut, 
                 highcut,
@@ -174,26 +188,24 @@ impl Plugin for PluginData {
                 eq8,
                 an_chan
             );
+        self.fft_processor.fft_effect.analyzer_layers.set_params(
+            self.params.analyzer_mode.value(),
+            self.params.analyzer_time.value(),
+            self.params.analyzer_rms_frames.value() as usize,
+            self.params.analyzer_peak_hold.value(),
+            self.params.analyzer_peak_decay.value(),
+        );
+        self.fft_processor.fft_effect.analyzer_layers.set_visible(
+            self.params.analyzer_show_input.value(),
+            self.params.analyzer_show_output.value(),
+            self.params.analyzer_show_aux.value(),
+        );
 
-        for (mut channel_samples, mut aux_channel_samples) in
-            buffer.iter_samples().zip(_aux.inputs[0].iter_samples())
-        {
-            let output_samples = self.stereo_fft_processor.process_sample(
-                [
-                    *channel_samples.get_mut(0).unwrap(),
-                    *channel_samples.get_mut(1).unwrap(),
-                ],
-                [
-                    *aux_channel_samples.get_mut(0).unwrap(),
-                    *aux_channel_samples.get_mut(1).unwrap(),
-                ],
-            );
-
-            *channel_samples.get_mut(0).unwrap() = output_samples[0];
-            *channel_samples.get_mut(1).unwrap() = output_samples[1];
-        }
+        let channels = buffer.as_slice();
+        layouts::upmix(channels, self.main_input_channels);
+        self.fft_processor.process_block_with_aux(channels, _aux.inputs[0].as_slice());
 
-        ProcessStatus::Normal
+        ProcessStatus::Tail(self.fft_processor.latency_samples() as u32)
     }
 
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
@@ -215,7 +227,7 @@ impl ClapPlugin for PluginData {
     const CLAP_SUPPORT_URL: Option<&'static str> = None;
 
     // Don't forget to change these features
-    const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::AudioEffect, ClapFeature::Stereo];
+    const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::AudioEffect, ClapFeature::Stereo, ClapFeature::Mono, ClapFeature::Surround];
 }
 
 impl Vst3Plugin for PluginData {
@@ -1,15 +1,20 @@
-use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+use std::sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex};
 
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
 
-use crate::{analyzer_data::AnalyzerChannel, editor, fft_core::fft_size::FFTSize, utils};
+use crate::{analyzer_data::AnalyzerChannel, editor};
+use spectral_core::{analyzer::{AnalyzerMode, MAX_RMS_FRAMES}, analyzer_axes::AnalyzerAxes, fft_size::FFTSize, overlap::Overlap, utils, window::WindowType, zero_padding::ZeroPadding};
 
 #[derive(Params)]
 pub struct PluginParams {
     #[persist = "editor-state"]
     pub editor_state: Arc<ViziaState>,
 
+    // the analyzer's zoomed and panned ranges, see `AnalyzerAxes::to_array`
+    #[persist = "analyzer-axes"]
+    pub analyzer_axes: Mutex<[f32; 4]>,
+
     #[id = "lowcut"]
     pub lowcut: FloatParam,
 
@@ -58,21 +63,99 @@ pub struct PluginParams {
     #[id = "stereo-link"]
     pub stereo_link: BoolParam,
 
+    #[id = "mix"]
+    pub mix: FloatParam,
+
+    #[id = "bypass"]
+    pub bypass: BoolParam,
+
     #[id = "fft-size"]
     pub fft_size: EnumParam<FFTSize>,
 
+    #[id = "overlap"]
+    pub overlap: EnumParam<Overlap>,
+
+    #[id = "zero-padding"]
+    pub zero_padding: EnumParam<ZeroPadding>,
+
+    #[id = "window-type"]
+    pub window_type: EnumParam<WindowType>,
+
+    #[id = "kaiser-beta"]
+    pub kaiser_beta: FloatParam,
+
     #[id = "analyzer-channel"]
     pub analyzer_channel: EnumParam<AnalyzerChannel>,
+
+    #[id = "analyzer-mode"]
+    pub analyzer_mode: EnumParam<AnalyzerMode>,
+
+    #[id = "analyzer-time"]
+    pub analyzer_time: FloatParam,
+
+    #[id = "analyzer-rms-frames"]
+    pub analyzer_rms_frames: IntParam,
+
+    #[id = "analyzer-peak-hold"]
+    pub analyzer_peak_hold: BoolParam,
+
+    #[id = "analyzer-peak-decay"]
+    pub analyzer_peak_decay: FloatParam,
+
+    #[id = "analyzer-show-input"]
+    pub analyzer_show_input: BoolParam,
+
+    #[id = "analyzer-show-output"]
+    pub analyzer_show_output: BoolParam,
+
+    #[id = "analyzer-show-aux"]
+    pub analyzer_show_aux: BoolParam,
 }
 
 impl PluginParams {
     pub fn new(size_callback: Arc<AtomicBool>) -> Self {
+        let overlap_callback = size_callback.clone();
+        let zero_padding_callback = size_callback.clone();
         Self {
             editor_state: editor::default_state(),
+            analyzer_axes: Mutex::new(AnalyzerAxes::default().to_array()),
+            mix: FloatParam::new("Mix", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_value_to_string(formatters::v2s_f32_percentage(2))
+                .with_string_to_value(formatters::s2v_f32_percentage())
+                .with_unit("%"),
+            bypass: BoolParam::new("Bypass", false).make_bypass(),
             fft_size: EnumParam::new("FFT Size", FFTSize::_1024).with_callback(Arc::new(
                 move |_| size_callback.store(true, Ordering::Release),
             )),
+            overlap: EnumParam::new("Overlap", Overlap::_4).with_callback(Arc::new(
+                move |_| overlap_callback.store(true, Ordering::Release),
+            )),
+            zero_padding: EnumParam::new("Zero Padding", ZeroPadding::_1).with_callback(Arc::new(
+                move |_| zero_padding_callback.store(true, Ordering::Release),
+            )),
+            window_type: EnumParam::new("Window", WindowType::Hann),
+            kaiser_beta: FloatParam::new("Kaiser Beta", 8.0, FloatRange::Linear { min: 0.0, max: 20.0 }),
             analyzer_channel: EnumParam::new("Analyzer Channel", AnalyzerChannel::Merged),
+            analyzer_mode: EnumParam::new("Analyzer Mode", AnalyzerMode::Average),
+            analyzer_time: FloatParam::new(
+                "Analyzer Time",
+                100.0,
+                FloatRange::Skewed { min: 10.0, max: 2_000.0, factor: FloatRange::skew_factor(-2.0) },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            analyzer_rms_frames: IntParam::new("Analyzer RMS Frames", 8, IntRange::Linear { min: 1, max: MAX_RMS_FRAMES as i32 }),
+            analyzer_peak_hold: BoolParam::new("Analyzer Peak Hold", true),
+            analyzer_peak_decay: FloatParam::new(
+                "Analyzer Peak Decay",
+                20.0,
+                FloatRange::Skewed { min: 1.0, max: 120.0, factor: FloatRange::skew_factor(-1.0) },
+            )
+            .with_unit(" dB/s")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            analyzer_show_input: BoolParam::new("Analyzer Input", true),
+            analyzer_show_output: BoolParam::new("Analyzer Output", true),
+            analyzer_show_aux: BoolParam::new("Analyzer Sidechain", false),
 
             lowcut: FloatParam::new(
                 "LowCut",
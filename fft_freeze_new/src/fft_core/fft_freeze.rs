@@ -1,87 +1,108 @@
 use nih_plug::nih_log;
 use rand::{rngs::{StdRng, ThreadRng}, Rng, SeedableRng};
 use realfft::{num_complex::{Complex, Complex32}, num_traits::Zero};
+use spectral_core::{analyzer::AnalyzerLayers, fft_data::{self, FFTData}, fft_size::FFTSize, spectral_effect::{SpectralEffect, SpectrumValues}, utils::fft_size_to_bins};
 
-use crate::utils::fft_size_to_bins;
+use crate::analyzer_data::{AnalyzerChannel, AnalyzerData};
 
 #[derive(Clone)]
 pub struct SpectralFrame {
     pub magnitudes: Vec<f32>,
     pub phases: Vec<f32>,
-    pub size: usize,
+    // how many of the bins were recorded, frames from before an fft size change have a different
+    // bin spacing than the spectra they're played back into
+    pub num_bins: usize,
 }
 
 impl SpectralFrame {
-    pub fn new(bins_size: usize) -> Self {
-        let mut mags = vec![0.0f32; 4096];
-        let mut phases = vec![0.0f32; 4096];
+    // sized for the largest fft, so a frame never has to be resized
+    pub fn new() -> Self {
         Self {
-            magnitudes: mags,
-            phases: phases,
-            size: bins_size,
+            magnitudes: vec![0.0f32; FFTSize::MAX.num_bins()],
+            phases: vec![0.0f32; FFTSize::MAX.num_bins()],
+            num_bins: FFTSize::MAX.num_bins(),
         }
     }
+
+    /// The recorded bin closest in frequency to `bin` of a spectrum with `num_bins` bins.
+    #[inline]
+    pub fn nearest_bin(&self, bin: usize, num_bins: usize) -> usize {
+        if num_bins == self.num_bins {
+            return bin;
+        }
+        (bin * (self.num_bins - 1) + (num_bins - 1) / 2) / (num_bins - 1)
+    }
+}
+
+impl Default for SpectralFrame {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct FFTFreeze {
-    pub frames: [Vec<SpectralFrame>; 2],
+    // ring buffer of frames for every channel
+    pub frames: Vec<Vec<SpectralFrame>>,
     idx: usize,
     pub rand: StdRng,
     current_rand_read_idx: usize,
     smooth_size: usize,
     stereo_link: bool,
+    // only the main band of the engine that's heard records and moves the playback position, see
+    // `SpectralEffect::crossfade_started`
+    band: usize,
 
     frozen: bool,
+
+    analyzer_input_data: triple_buffer::Input<AnalyzerData>,
+    analyzer_channel: AnalyzerChannel,
+    pub analyzer_layers: AnalyzerLayers,
 }
 
 impl FFTFreeze {
-    pub fn new(bins_size: usize, frames_num: usize) -> Self {
+    pub fn new(frames_num: usize, analyzer_buffer: triple_buffer::Input<AnalyzerData>) -> Self {
         Self {
-            frames: [vec![SpectralFrame::new(bins_size); frames_num], vec![SpectralFrame::new(bins_size); frames_num]],
+            frames: vec![vec![SpectralFrame::new(); frames_num]],
             idx: 0,
             rand: StdRng::from_entropy(),
             current_rand_read_idx: 0,
             smooth_size: 4,
             stereo_link: true,
+            band: 0,
             frozen: false,
+            analyzer_input_data: analyzer_buffer,
+            analyzer_channel: AnalyzerChannel::Merged,
+            analyzer_layers: AnalyzerLayers::new(),
         }
     }
 
-    pub fn resize(&mut self, size: usize) {
-        // for channel in 0..2 {
-        //     for frame in self.frames[channel].iter_mut() {
-        //         frame.magnitudes.resize(fft_size_to_bins(size), 0.0f32);
-        //         frame.phases.resize(fft_size_to_bins(size), 0.0f32);
-        //     }
-        // }
-        for channel in 0..2 {
-            for frame in self.frames[channel].iter_mut() {
-                frame.size = fft_size_to_bins(size);
-            }
-        }
-    }
-
-    pub fn set_params(&mut self, frozen: bool, stereo_link: bool) {
+    pub fn set_params(&mut self, an_chan: AnalyzerChannel, frozen: bool, stereo_link: bool) {
+        self.analyzer_channel = an_chan;
         self.frozen = frozen;
         self.stereo_link = stereo_link;
     }
 
-    /// Stores a stereo spectral frame to a ring buffer of N previous frames
-    pub fn record(&mut self, mags: [&Vec<f32>; 2], phases: [&Vec<f32>; 2]) {
-        for channel in 0..2 {
-            for (i, (mag, phase)) in mags[channel].iter().zip(phases[channel].iter()).enumerate() {
-                self.frames[channel][self.idx].magnitudes[i] = *mag;
-                self.frames[channel][self.idx].phases[i] = *phase;
-            }
-            self.idx += 1;
-            if self.idx == self.frames[channel].len() {
-                self.idx = 0;
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Stores a spectral frame of every channel to a ring buffer of N previous frames
+    pub fn record(&mut self, input: &[FFTData]) {
+        for (channel, data) in input.iter().enumerate() {
+            let frame = &mut self.frames[channel][self.idx];
+            for (i, (mag, phase)) in data.spectrum_mag.iter().zip(data.spectrum_phase.iter()).enumerate() {
+                frame.magnitudes[i] = *mag;
+                frame.phases[i] = *phase;
             }
+            frame.num_bins = data.spectrum_mag.len();
+        }
+        self.idx += 1;
+        if self.idx == self.frames[0].len() {
+            self.idx = 0;
         }
     }
 
     pub fn get_random_walk_next_frame_idx(&mut self) -> usize {
-        let mut out_idx = 0usize;
         if self.current_rand_read_idx == 0 {
             self.current_rand_read_idx += 1;
         }
@@ -97,7 +118,12 @@ impl FFTFreeze {
             }
         }
 
-        out_idx = self.current_rand_read_idx + self.idx;
+        self.read_idx()
+    }
+
+    /// The frame the random walk is at, without moving it.
+    pub fn read_idx(&self) -> usize {
+        let mut out_idx = self.current_rand_read_idx + self.idx;
         if out_idx >= self.frames[0].len() {
             out_idx -= self.frames[0].len();
         }
@@ -109,39 +135,76 @@ impl FFTFreeze {
         (((idx % self.frames[0].len() as isize) + self.frames[0].len() as isize) % self.frames[0].len() as isize) as usize
     }
 
-    pub fn process_spectrum(&mut self, 
-        mag: [&Vec<f32>; 2], 
-        phase: [&Vec<f32>; 2], 
-        db: [&Vec<f32>; 2], 
-        freq: [&Vec<f32>; 2], 
-        output_buffer: &mut [Vec<Complex<f32>>; 2]) 
-    {
+}
+
+impl SpectralEffect for FFTFreeze {
+    fn set_num_channels(&mut self, num_channels: usize) {
+        let frames = self.frames[0].clone();
+        self.frames.resize(num_channels, frames);
+    }
+
+    // the recorded frames are dropped as well, a freeze after a reset only holds what came after it
+    fn reset(&mut self) {
+        self.analyzer_layers.reset();
+        for frame in self.frames.iter_mut().flatten() {
+            frame.magnitudes.fill(0.0);
+            frame.phases.fill(0.0);
+        }
+        self.idx = 0;
+        self.current_rand_read_idx = 0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.analyzer_layers.set_hop_rate(sample_rate, hop_size);
+    }
+
+    fn set_band(&mut self, band: usize) {
+        self.band = band;
+    }
+
+    // works on magnitudes and phases, but doesn't need them in dB or tracked over time
+    fn spectrum_values(&self) -> SpectrumValues {
+        SpectrumValues {
+            magnitude: true,
+            phase: true,
+            ..SpectrumValues::NONE
+        }
+    }
+
+    fn process_spectrum(
+        &mut self,
+        input: &[FFTData],
+        _aux: Option<&[FFTData]>,
+        output_buffer: &mut [Vec<Complex<f32>>],
+    ) {
+        // the outgoing engine of a crossfade plays back the same frames, but doesn't record or walk
+        let main_band = self.band == 0;
+
         // if we're not frozen, record the frame and move on
         if !self.frozen {
-            self.record(mag, phase);
+            if main_band {
+                self.record(input);
+            }
             
             // do nothing, simply forward the data to output buffer
-            for channel in 0..2 {
-                for (i, bin) in output_buffer[channel].iter_mut().enumerate() {
-                    *bin = Complex::from_polar(mag[channel][i], phase[channel][i])
-                }
+            for (data, output) in input.iter().zip(output_buffer.iter_mut()) {
+                output.copy_from_slice(&data.fft_out);
             }
 
             return;
         }
 
-        let mut idxs: [usize; 2] = [0, 0];
-        idxs[0] = self.get_random_walk_next_frame_idx();
-        // if stereo-link is on, that means both left and right channels use the same random idx to preserve timing of frames
-        // if it's off, then both channels get separate indices for stereo randomness
-        idxs[1] = if self.stereo_link {
-            idxs[0]
-        } else {
-            self.get_random_walk_next_frame_idx()
-        };
+        let linked_idx = if main_band { self.get_random_walk_next_frame_idx() } else { self.read_idx() };
+        for channel in 0..output_buffer.len() {
+            // if stereo-link is on, that means all channels use the same random idx to preserve timing of frames
+            // if it's off, then every channel gets a separate index for stereo randomness
+            let idx = if channel == 0 || self.stereo_link || !main_band {
+                linked_idx
+            } else {
+                self.get_random_walk_next_frame_idx()
+            };
 
-        for (channel, idx) in (0..2).into_iter().zip(idxs) {
-            
+            let num_bins = output_buffer[channel].len();
             for (i, out_complex) in output_buffer[channel].iter_mut().enumerate() {
                 let mut mag = 0.0f32;
                 let mut phase = 0.0f32;
@@ -152,20 +215,100 @@ impl FFTFreeze {
                     let x = o as f32;
                     let weight = (-((x - a) / a).abs()) + 1.3f32;
                     //nih_log!("idx {} + o {} = {}", idx, o, idx+o);
-                    mag += self.frames[channel][self.wrap_index((idx + o) as isize)].magnitudes[i] * weight;
-                    phase += self.frames[channel][self.wrap_index((idx + o) as isize)].phases[i] * weight;
+                    let frame = &self.frames[channel][self.wrap_index((idx + o) as isize)];
+                    let bin = frame.nearest_bin(i, num_bins);
+                    mag += frame.magnitudes[bin] * weight;
+                    phase += frame.phases[bin] * weight;
                     sum += weight
                 }
                 *out_complex = Complex::from_polar(mag / sum, phase / sum)
             }
         }
     }
+
+    fn analyze(&mut self, output: &[FFTData], _aux: Option<&[FFTData]>, fft_size: usize) {
+        let num_bins = fft_size_to_bins(fft_size);
+        let analyzer_input = self.analyzer_input_data.input_buffer();
+        analyzer_input.magnitudes.fill(0.0f32);
+        analyzer_input.num_bins = num_bins;
+        // mono only has a left channel
+        let right = output.get(1).unwrap_or(&output[0]);
+        for (i, mag) in analyzer_input.magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].spectrum_db[i],
+                AnalyzerChannel::Right => right.spectrum_db[i],
+                AnalyzerChannel::Merged => fft_data::average_db(output, i),
+            };
+        }
+        for (i, mag) in analyzer_input.input_magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].analyzer_input_db[i],
+                AnalyzerChannel::Right => right.analyzer_input_db[i],
+                AnalyzerChannel::Merged => fft_data::average_input_db(output, i),
+            };
+        }
+        self.analyzer_layers.process_spectra(analyzer_input, false);
+        self.analyzer_input_data.publish();
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::f32::consts::TAU;
+
+    use spectral_core::fft_processor::FFTProcessor;
+    use triple_buffer::TripleBuffer;
+
     use super::*;
 
+    // power of `samples` at `freq`, a single bin of a DFT as long as `samples`
+    fn power_at(samples: &[f32], freq: f32, sample_rate: f32) -> f32 {
+        let (re, im) = samples.iter().enumerate().fold((0f32, 0f32), |(re, im), (n, x)| {
+            let phase = TAU * freq * n as f32 / sample_rate;
+            (re + x * phase.cos(), im + x * phase.sin())
+        });
+        re * re + im * im
+    }
+
+    #[test]
+    fn freeze_across_fft_size_change_test() {
+        let sample_rate = 44100;
+        let (analyzer_input, _analyzer_output) = TripleBuffer::new(&AnalyzerData::new(FFTSize::MAX.num_bins())).split();
+        let mut processor = FFTProcessor::new(sample_rate, 1, 1024, 4, FFTFreeze::new(64, analyzer_input));
+        let sine = |i: usize| (TAU * 1000.0 * i as f32 / sample_rate as f32).sin() * 0.5;
+
+        // the ring ends up with frames of both sizes
+        let mut i = 0;
+        for _ in 0..sample_rate {
+            processor.process_sample(&mut [sine(i)]);
+            i += 1;
+        }
+        processor.crossfade_to(4096, 4, 1);
+        while processor.is_crossfading() {
+            processor.process_sample(&mut [sine(i)]);
+            i += 1;
+        }
+        for _ in 0..4096 {
+            processor.process_sample(&mut [sine(i)]);
+            i += 1;
+        }
+
+        processor.fft_effect.set_params(AnalyzerChannel::Merged, true, true);
+        let output: Vec<f32> = (0..sample_rate)
+            .map(|_| {
+                let mut sample = [0.0];
+                processor.process_sample(&mut sample);
+                sample[0]
+            })
+            .collect();
+        // frames recorded at 1024 would be at a quarter of the frequency if they were played back
+        // at the bin spacing of 4096
+        let output = &output[8192..];
+        let tone = power_at(output, 1000.0, sample_rate as f32);
+        let quarter = power_at(output, 250.0, sample_rate as f32);
+        assert!(tone > 100.0 * quarter, "1000 Hz: {tone}, 250 Hz: {quarter}");
+    }
+
     #[test]
     fn it_works() {
         let len = 10;
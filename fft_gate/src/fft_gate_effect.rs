@@ -1,54 +1,108 @@
 use realfft::num_complex::Complex;
+use spectral_core::{analyzer::AnalyzerLayers, fft_data::{self, FFTData}, smoothing::HopSmoother, spectral_effect::{SpectralEffect, SpectrumValues}, utils};
 
-use crate::{gate::Gate, utils};
+use crate::analyzer_data::{AnalyzerChannel, AnalyzerData};
 
 pub struct FFTGateEffect {
-    gates: Vec<Gate>,
-    threshold: f32,
+    threshold_smoother: HopSmoother,
+    // the current threshold as a squared magnitude, so bins can be compared without a log or a sqrt
+    threshold_power: f32,
+    band: usize,
+    analyzer_input_data: triple_buffer::Input<AnalyzerData>,
+    analyzer_channel: AnalyzerChannel,
+    pub analyzer_layers: AnalyzerLayers,
 }
 
 impl FFTGateEffect {
-    pub fn new(size: usize) -> Self {
+    pub fn new(analyzer_buffer: triple_buffer::Input<AnalyzerData>) -> Self {
         Self {
-            gates: vec![Gate::new(); size],
-            threshold: -100f32,
+            threshold_smoother: HopSmoother::new(-100f32),
+            threshold_power: 0f32,
+            band: 0,
+            analyzer_input_data: analyzer_buffer,
+            analyzer_channel: AnalyzerChannel::Merged,
+            analyzer_layers: AnalyzerLayers::new(),
         }
     }
 
-    pub fn resize(&mut self, size: usize) {
-        self.gates.resize(size, Gate::new_with_threshold(self.threshold));
+    pub fn set_params(&mut self, an_chan: AnalyzerChannel, th: f32) {
+        self.analyzer_channel = an_chan;
+        self.set_threshold(th);
     }
 
     pub fn set_threshold(&mut self, th: f32) {
-        self.threshold = th;
-        for gate in self.gates.iter_mut() {
-            gate.set_gate_params(th);
-        }
+        self.threshold_smoother.set_target(th);
+    }
+}
+
+impl SpectralEffect for FFTGateEffect {
+    fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.analyzer_layers.set_hop_rate(sample_rate, hop_size);
+        self.threshold_smoother.set_hop_rate(sample_rate, hop_size);
+    }
+
+    fn set_band(&mut self, band: usize) {
+        self.band = band;
+    }
+
+    fn reset(&mut self) {
+        self.analyzer_layers.reset();
+        self.threshold_smoother.reset();
+        self.threshold_power = utils::db_to_gain(self.threshold_smoother.value()).powi(2);
     }
 
-    pub fn process_spectrum(&mut self, 
-        mag: [&Vec<f32>; 2], 
-        phase: [&Vec<f32>; 2], 
-        db: [&Vec<f32>; 2], 
-        freq: [&Vec<f32>; 2], 
-        output_buffer: &mut [Vec<Complex<f32>>; 2])
-    {
-        let len = output_buffer.len() - 1;
-        output_buffer[0][0] = Complex::from_polar(0f32, 0f32);
-        output_buffer[1][0] = Complex::from_polar(0f32, 0f32);
-        output_buffer[0][len] = Complex::from_polar(0f32, 0f32);
-        output_buffer[1][len] = Complex::from_polar(0f32, 0f32);
-        for channel in 0..2 {
-            for i in 1..(output_buffer[0].len() - 1) {
-                let mut out: f32 = db[channel][i];
-                if db[channel][i] < self.threshold {
-                    out = -100.0f32;
-                }
-                output_buffer[channel][i] = Complex::from_polar(
-                    utils::db_to_gain(out),
-                     phase[channel][i]
-                    );
+    fn spectrum_values(&self) -> SpectrumValues {
+        SpectrumValues::NONE
+    }
+
+    fn process_spectrum(
+        &mut self,
+        input: &[FFTData],
+        _aux: Option<&[FFTData]>,
+        output_buffer: &mut [Vec<Complex<f32>>],
+    ) {
+        // the threshold moves once per hop of the main band
+        if self.band == 0 {
+            self.threshold_power = utils::db_to_gain(self.threshold_smoother.next_hop()).powi(2);
+        }
+
+        let len = output_buffer[0].len() - 1;
+        for channel in 0..input.len() {
+            output_buffer[channel][0] = Complex::from_polar(0f32, 0f32);
+            output_buffer[channel][len] = Complex::from_polar(0f32, 0f32);
+            let bins = &input[channel].fft_out;
+            for i in 1..len {
+                output_buffer[channel][i] = if bins[i].norm_sqr() < self.threshold_power {
+                    Complex::from_polar(0f32, 0f32)
+                } else {
+                    bins[i]
+                };
             }
         }
     }
+
+    fn analyze(&mut self, output: &[FFTData], _aux: Option<&[FFTData]>, fft_size: usize) {
+        let num_bins = utils::fft_size_to_bins(fft_size);
+        let analyzer_input = self.analyzer_input_data.input_buffer();
+        analyzer_input.magnitudes.fill(0.0f32);
+        analyzer_input.num_bins = num_bins;
+        // mono only has a left channel
+        let right = output.get(1).unwrap_or(&output[0]);
+        for (i, mag) in analyzer_input.magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].spectrum_db[i],
+                AnalyzerChannel::Right => right.spectrum_db[i],
+                AnalyzerChannel::Merged => fft_data::average_db(output, i),
+            };
+        }
+        for (i, mag) in analyzer_input.input_magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].analyzer_input_db[i],
+                AnalyzerChannel::Right => right.analyzer_input_db[i],
+                AnalyzerChannel::Merged => fft_data::average_input_db(output, i),
+            };
+        }
+        self.analyzer_layers.process_spectra(analyzer_input, false);
+        self.analyzer_input_data.publish();
+    }
 }
@@ -1,31 +1,28 @@
 use analyzer_data::{AnalyzerChannel, AnalyzerData};
-use fft_core::{fft_size::FFTSize, stereo_fft_processor::StereoFFTProcessor};
 use fft_gate_effect::FFTGateEffect;
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
+use spectral_core::{analyzer::{AnalyzerMode, MAX_RMS_FRAMES}, analyzer_axes::AnalyzerAxes, channel_mode::ChannelMode, fft_processor::FFTProcessor, fft_size::FFTSize, layouts, overlap::Overlap, resolution::{self, Resolution}, spectrogram::{self, Colormap}, transient, window::WindowType, zero_padding::ZeroPadding};
 use triple_buffer::TripleBuffer;
 use util::db_to_gain;
 use std::{any::{self, Any}, env, f32::consts::PI, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}};
 
 mod editor;
-mod utils;
-mod gate;
 mod analyzer_data;
-mod fft_core;
 mod fft_gate_effect;
 
 // This is a shortened version of the gain example with most comments removed, check out
 // https://github.com/robbert-vdh/nih-plug/blob/master/plugins/examples/gain/src/lib.rs to get
 // started
 
-const WINDOW_CORRECTION: f32 = 2.0 / 3.0;
-
 pub struct FFTGate {
-    stereo_fft_processor: StereoFFTProcessor,
+    fft_processor: FFTProcessor<FFTGateEffect>,
     params: Arc<FFTGateParams>,
     analyzer_output_data: Arc<Mutex<triple_buffer::Output<AnalyzerData>>>,
     sample_rate: Arc<AtomicF32>,
     size_changed: Arc<AtomicBool>,
+    // a mono input on a stereo output only fills the first channel
+    main_input_channels: usize,
 }
 
 #[derive(Params)]
@@ -33,40 +30,186 @@ pub struct FFTGateParams {
     #[persist = "editor-state"]
     editor_state: Arc<ViziaState>,
 
+    // the analyzer's zoomed and panned ranges, see `AnalyzerAxes::to_array`
+    #[persist = "analyzer-axes"]
+    analyzer_axes: Mutex<[f32; 4]>,
+
+    #[id = "mix"]
+    mix: FloatParam,
+
+    #[id = "bypass"]
+    bypass: BoolParam,
+
     #[id = "fft-size"]
     fft_size: EnumParam<FFTSize>,
 
+    #[id = "overlap"]
+    overlap: EnumParam<Overlap>,
+
+    #[id = "zero-padding"]
+    zero_padding: EnumParam<ZeroPadding>,
+
+    #[id = "resolution"]
+    resolution: EnumParam<Resolution>,
+
+    #[id = "low-crossover"]
+    low_crossover: FloatParam,
+
+    #[id = "high-crossover"]
+    high_crossover: FloatParam,
+
+    #[id = "transient-switching"]
+    transient_switching: BoolParam,
+
+    #[id = "transient-threshold"]
+    transient_threshold: FloatParam,
+
+    #[id = "window-type"]
+    window_type: EnumParam<WindowType>,
+
+    #[id = "kaiser-beta"]
+    kaiser_beta: FloatParam,
+
+    #[id = "channel-mode"]
+    channel_mode: EnumParam<ChannelMode>,
+
     #[id = "analyzer-channel"]
     analyzer_channel: EnumParam<AnalyzerChannel>,
 
+    #[id = "analyzer-mode"]
+    analyzer_mode: EnumParam<AnalyzerMode>,
+
+    #[id = "analyzer-time"]
+    analyzer_time: FloatParam,
+
+    #[id = "analyzer-rms-frames"]
+    analyzer_rms_frames: IntParam,
+
+    #[id = "analyzer-peak-hold"]
+    analyzer_peak_hold: BoolParam,
+
+    #[id = "analyzer-peak-decay"]
+    analyzer_peak_decay: FloatParam,
+
+    #[id = "analyzer-show-input"]
+    analyzer_show_input: BoolParam,
+
+    #[id = "analyzer-show-output"]
+    analyzer_show_output: BoolParam,
+
+    #[id = "spectrogram-colormap"]
+    spectrogram_colormap: EnumParam<Colormap>,
+
+    #[id = "spectrogram-min-db"]
+    spectrogram_min_db: FloatParam,
+
+    #[id = "spectrogram-max-db"]
+    spectrogram_max_db: FloatParam,
+
     #[id = "threshold"]
     threshold: FloatParam,
 }
 
 impl Default for FFTGate {
     fn default() -> Self {
-        let (analyzer_input_data, analyzer_output_data) = TripleBuffer::new(&AnalyzerData::new(utils::fft_size_to_bins(FFTSize::_4096 as usize), 44100)).split();
+        let (analyzer_input_data, analyzer_output_data) = TripleBuffer::new(&AnalyzerData::new(FFTSize::MAX.num_bins())).split();
         let size_changed = Arc::new(AtomicBool::new(false));
         
         Self {
-            stereo_fft_processor: StereoFFTProcessor::new(44100, FFTSize::_1024 as usize, size_changed.clone(), analyzer_input_data),
+            fft_processor: FFTProcessor::new(44100, 2, FFTSize::_1024 as usize, Overlap::_4 as usize, FFTGateEffect::new(analyzer_input_data)),
             params: Arc::new(FFTGateParams::new(size_changed.clone())),
             analyzer_output_data: Arc::new(Mutex::new(analyzer_output_data)),
-            sample_rate: Arc::new(AtomicF32::new(1.0)),
+            sample_rate: Arc::new(AtomicF32::new(44100.0)),
             size_changed: size_changed.clone(),
+            main_input_channels: 2,
         }
     }
 }
 
 impl FFTGateParams {
     fn new(size_callback: Arc<AtomicBool>) -> Self {
+        let overlap_callback = size_callback.clone();
+        let zero_padding_callback = size_callback.clone();
+        let resolution_callback = size_callback.clone();
         Self {
             editor_state: editor::default_state(),
+            analyzer_axes: Mutex::new(AnalyzerAxes::default().to_array()),
+            mix: FloatParam::new("Mix", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_value_to_string(formatters::v2s_f32_percentage(2))
+                .with_string_to_value(formatters::s2v_f32_percentage())
+                .with_unit("%"),
+            bypass: BoolParam::new("Bypass", false).make_bypass(),
             fft_size: EnumParam::new("FFT Size", FFTSize::_1024)
             .with_callback(Arc::new(move |_| {
                     size_callback.store(true, Ordering::Release)
                 })),
+            overlap: EnumParam::new("Overlap", Overlap::_4).with_callback(Arc::new(
+                move |_| overlap_callback.store(true, Ordering::Release),
+            )),
+            zero_padding: EnumParam::new("Zero Padding", ZeroPadding::_1).with_callback(Arc::new(
+                move |_| zero_padding_callback.store(true, Ordering::Release),
+            )),
+            resolution: EnumParam::new("Resolution", Resolution::Single).with_callback(Arc::new(
+                move |_| resolution_callback.store(true, Ordering::Release),
+            )),
+            low_crossover: FloatParam::new(
+                "Low Crossover",
+                resolution::DEFAULT_LOW_CROSSOVER_HZ,
+                FloatRange::Skewed { min: 50.0, max: 2_000.0, factor: 0.6 },
+            )
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(1)),
+            high_crossover: FloatParam::new(
+                "High Crossover",
+                resolution::DEFAULT_HIGH_CROSSOVER_HZ,
+                FloatRange::Skewed { min: 1_000.0, max: 16_000.0, factor: 0.6 },
+            )
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(1)),
+            transient_switching: BoolParam::new("Transient Switching", false),
+            transient_threshold: FloatParam::new(
+                "Transient Threshold",
+                transient::DEFAULT_THRESHOLD_DB,
+                FloatRange::Linear { min: 3.0, max: 24.0 },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            window_type: EnumParam::new("Window", WindowType::Hann),
+            kaiser_beta: FloatParam::new("Kaiser Beta", 8.0, FloatRange::Linear { min: 0.0, max: 20.0 }),
+            channel_mode: EnumParam::new("Channel Mode", ChannelMode::Stereo),
             analyzer_channel: EnumParam::new("Analyzer Channel", AnalyzerChannel::Merged),
+            analyzer_mode: EnumParam::new("Analyzer Mode", AnalyzerMode::Average),
+            analyzer_time: FloatParam::new(
+                "Analyzer Time",
+                100.0,
+                FloatRange::Skewed { min: 10.0, max: 2_000.0, factor: FloatRange::skew_factor(-2.0) },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            analyzer_rms_frames: IntParam::new("Analyzer RMS Frames", 8, IntRange::Linear { min: 1, max: MAX_RMS_FRAMES as i32 }),
+            analyzer_peak_hold: BoolParam::new("Analyzer Peak Hold", true),
+            analyzer_peak_decay: FloatParam::new(
+                "Analyzer Peak Decay",
+                20.0,
+                FloatRange::Skewed { min: 1.0, max: 120.0, factor: FloatRange::skew_factor(-1.0) },
+            )
+            .with_unit(" dB/s")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            analyzer_show_input: BoolParam::new("Analyzer Input", true),
+            analyzer_show_output: BoolParam::new("Analyzer Output", true),
+            spectrogram_colormap: EnumParam::new("Spectrogram Colormap", Colormap::Magma),
+            spectrogram_min_db: FloatParam::new(
+                "Spectrogram Floor",
+                spectrogram::DEFAULT_MIN_DB,
+                FloatRange::Linear { min: -150.0, max: -30.0 },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            spectrogram_max_db: FloatParam::new(
+                "Spectrogram Ceiling",
+                spectrogram::DEFAULT_MAX_DB,
+                FloatRange::Linear { min: -60.0, max: 20.0 },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
 
             threshold: FloatParam::new(
                 "Threshold", 
@@ -92,18 +235,7 @@ impl Plugin for FFTGate{
 
     // The first audio IO layout is used as the default. The other layouts may be selected either
     // explicitly or automatically by the host or the user depending on the plugin API/backend.
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
-
-        aux_input_ports: &[],
-        aux_output_ports: &[],
-
-        // Individual ports and the layout as a whole can be named here. By default these names
-        // are generated as needed. This layout will be called 'Stereo', while a layout with
-        // only one input and output channel would be called 'Mono'.
-        names: PortNames::const_default(),
-    }];
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = layouts::MAIN_LAYOUTS;
 
     const MIDI_INPUT: MidiConfig = MidiConfig::None;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
@@ -133,10 +265,16 @@ impl Plugin for FFTGate{
         // Resize buffers and perform other potentially expensive initialization operations here.
         // The `reset()` function is always called right after this function. You can remove this
         // function if you do not need it.
-        self.stereo_fft_processor.set_sample_rate(_buffer_config.sample_rate as usize);
+        self.fft_processor.set_sample_rate(_buffer_config.sample_rate as usize);
         let new_size = self.params.fft_size.value();
-        _context.set_latency_samples(new_size as u32);
-        self.stereo_fft_processor.change_fft_size(new_size as usize);
+        let (num_channels, input_channels) = layouts::channel_counts(_audio_io_layout);
+        self.main_input_channels = input_channels;
+        self.fft_processor.set_num_channels(num_channels);
+        self.fft_processor.change_overlap(self.params.overlap.value() as usize);
+        self.fft_processor.change_zero_padding(self.params.zero_padding.value() as usize);
+        self.fft_processor.change_resolution(self.params.resolution.value());
+        self.fft_processor.change_fft_size(new_size as usize);
+        _context.set_latency_samples(self.fft_processor.latency_samples() as u32);
         self.sample_rate.store(_buffer_config.sample_rate, std::sync::atomic::Ordering::Relaxed);
         true
     }
@@ -145,7 +283,11 @@ impl Plugin for FFTGate{
         // Reset buffers and envelopes here. This can be called from the audio thread and may not
         // allocate. You can remove this function if you do not need it.
         let new_size = self.params.fft_size.value();
-        self.stereo_fft_processor.change_fft_size(new_size as usize);
+        self.fft_processor.change_overlap(self.params.overlap.value() as usize);
+        self.fft_processor.change_zero_padding(self.params.zero_padding.value() as usize);
+        self.fft_processor.change_resolution(self.params.resolution.value());
+        self.fft_processor.change_fft_size(new_size as usize);
+        self.fft_processor.reset();
     }
 
     fn process(
@@ -160,25 +302,45 @@ impl Plugin for FFTGate{
         let fft_size = self.params.fft_size.value();
 
         if self.size_changed.load(Ordering::Relaxed) {
-            self.stereo_fft_processor.change_fft_size(fft_size as usize);
-            _context.set_latency_samples(fft_size as u32);
-            self.size_changed.store(false, Ordering::Relaxed);
-        }
-
-        self.stereo_fft_processor.set_params(an_chan, th);
-
-        for mut channel_samples in buffer.iter_samples() {
-            // Smoothing is optionally built into the parameters themselves
-            let output_samples = self.stereo_fft_processor.process_sample(
-                [*channel_samples.get_mut(0).unwrap(), 
-                *channel_samples.get_mut(1).unwrap()]
+            self.fft_processor.crossfade_to_resolution(
+                fft_size as usize,
+                self.params.overlap.value() as usize,
+                self.params.zero_padding.value() as usize,
+                self.params.resolution.value(),
             );
-            
-            *channel_samples.get_mut(0).unwrap() = output_samples[0];
-            *channel_samples.get_mut(1).unwrap() = output_samples[1];
+            _context.set_latency_samples(self.fft_processor.latency_samples() as u32);
+            self.size_changed.store(false, Ordering::Relaxed);
         }
 
-        ProcessStatus::Normal
+        self.fft_processor.set_window(self.params.window_type.value(), self.params.kaiser_beta.value());
+        self.fft_processor.set_mix(self.params.mix.value());
+        self.fft_processor.set_bypass(self.params.bypass.value());
+        // nobody sees the analyzer while the editor is closed
+        self.fft_processor.set_analyzer_active(self.params.editor_state.is_open());
+        self.fft_processor.set_channel_mode(self.params.channel_mode.value());
+        self.fft_processor.set_crossovers(self.params.low_crossover.value(), self.params.high_crossover.value());
+        self.fft_processor.set_transient_threshold(self.params.transient_threshold.value());
+        self.fft_processor.set_transient_switching(self.params.transient_switching.value());
+
+        self.fft_processor.fft_effect.set_params(an_chan, th);
+        self.fft_processor.fft_effect.analyzer_layers.set_params(
+            self.params.analyzer_mode.value(),
+            self.params.analyzer_time.value(),
+            self.params.analyzer_rms_frames.value() as usize,
+            self.params.analyzer_peak_hold.value(),
+            self.params.analyzer_peak_decay.value(),
+        );
+        self.fft_processor.fft_effect.analyzer_layers.set_visible(
+            self.params.analyzer_show_input.value(),
+            self.params.analyzer_show_output.value(),
+            false,
+        );
+
+        let channels = buffer.as_slice();
+        layouts::upmix(channels, self.main_input_channels);
+        self.fft_processor.process_block(channels);
+
+        ProcessStatus::Tail(self.fft_processor.latency_samples() as u32)
     }
 
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
@@ -200,7 +362,7 @@ impl ClapPlugin for FFTGate {
     const CLAP_SUPPORT_URL: Option<&'static str> = None;
 
     // Don't forget to change these features
-    const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::AudioEffect, ClapFeature::Stereo];
+    const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::AudioEffect, ClapFeature::Stereo, ClapFeature::Mono, ClapFeature::Surround];
 }
 
 impl Vst3Plugin for FFTGate {
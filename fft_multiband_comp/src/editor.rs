@@ -1,6 +1,5 @@
 use std::sync::{Arc, Mutex};
 
-use analyzer::Analyzer;
 use nih_plug::prelude::{util, AtomicF32, Editor, Vst3Plugin};
 use nih_plug_vizia::vizia::image::{Pixel, Pixels};
 use nih_plug_vizia::vizia::style::Color;
@@ -8,6 +7,8 @@ use nih_plug_vizia::vizia::vg::Canvas;
 use nih_plug_vizia::vizia::{prelude::*, vg};
 use nih_plug_vizia::widgets::*;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
+use spectral_core::editor::{analyzer::Analyzer, spectrogram::Spectrogram, AnalyzerParams, SpectrogramParams};
+use spectral_core::spectrogram::Colormap;
 use param_knob::ParamKnob;
 use peak_curve::{Band, PeakCurve};
 
@@ -22,10 +23,11 @@ pub const COMFORTAA_LIGHT_TTF: &[u8] = include_bytes!("../res/Comfortaa-Light.tt
 pub const COMFORTAA: &str = "Comfortaa";
 
 const WIDTH: u32 = 850;
-const HEIGHT: u32 = 700; 
+const HEIGHT: u32 = 870; 
 
 const ANALYZER_WIDTH: f32 = 800.0;
 const ANALYZER_HEIGHT: f32 = 225.0;
+const SPECTROGRAM_HEIGHT: f32 = 120.0;
 
 const TOP_KNOB_SPACE_WIDTH: f32 = 120.0f32;
 
@@ -45,6 +47,18 @@ pub struct EditorData {
 
 impl Model for EditorData {}
 
+impl AnalyzerParams for PluginParams {
+    fn analyzer_axes(&self) -> &Mutex<[f32; 4]> {
+        &self.analyzer_axes
+    }
+}
+
+impl SpectrogramParams for PluginParams {
+    fn spectrogram_colors(&self) -> (Colormap, f32, f32) {
+        (self.spectrogram_colormap.value(), self.spectrogram_min_db.value(), self.spectrogram_max_db.value())
+    }
+}
+
 pub(crate) fn create(
     editor_state: Arc<ViziaState>,
     editor_data: EditorData,
@@ -109,7 +123,7 @@ pub(crate) fn create(
             .child_left(Stretch(1.0))
             .child_right(Stretch(1.0));
 
-            Analyzer::new(cx, EditorData::analyzer_data, EditorData::sample_rate)
+            Analyzer::new(cx, EditorData::plugin_data, EditorData::analyzer_data, EditorData::sample_rate)
                 .left(Stretch(1.0))
                 .right(Stretch(1.0))
                 .width(Pixels(ANALYZER_WIDTH))
@@ -129,6 +143,33 @@ pub(crate) fn create(
             .top(Pixels(10.0))
             .height(Pixels(35.0))
             .width(Pixels(ANALYZER_WIDTH));
+            Spectrogram::new(cx, EditorData::plugin_data, EditorData::analyzer_data, EditorData::sample_rate)
+                .left(Stretch(1.0))
+                .right(Stretch(1.0))
+                .width(Pixels(ANALYZER_WIDTH))
+                .height(Pixels(SPECTROGRAM_HEIGHT))
+                .border_color(SPECTRUM_BORDER_COLOR)
+                .border_width(Pixels(4.0));
+            HStack::new(cx, |cx| {
+                ParamSlider::new(cx, EditorData::plugin_data, |params| &params.spectrogram_colormap)
+                .left(Stretch(1.0))
+                .right(Pixels(50.0));
+                ParamSlider::new(cx, EditorData::plugin_data, |params| &params.spectrogram_min_db)
+                .set_style(ParamSliderStyle::FromLeft)
+                .right(Pixels(50.0));
+                ParamSlider::new(cx, EditorData::plugin_data, |params| &params.spectrogram_max_db)
+                .set_style(ParamSliderStyle::FromLeft)
+                .right(Pixels(50.0));
+                ParamButton::new(cx, EditorData::plugin_data, |params| &params.analyzer_show_input)
+                .with_label("Input")
+                .right(Pixels(10.0));
+                ParamButton::new(cx, EditorData::plugin_data, |params| &params.analyzer_show_output)
+                .with_label("Output")
+                .right(Stretch(1.0));
+            })
+            .top(Pixels(10.0))
+            .height(Pixels(35.0))
+            .width(Pixels(ANALYZER_WIDTH));
             HStack::new(cx, |cx|{
                 VStack::new(cx, |cx| {
                     HStack::new(cx, |cx| {
@@ -2,7 +2,8 @@ use std::sync::{Arc, Mutex};
 use nih_plug::nih_log;
 
 use nih_plug_vizia::vizia::{prelude::*, vg};
-use crate::{analyzer_data::AnalyzerData, utils};
+use crate::analyzer_data::AnalyzerData;
+use spectral_core::utils;
 
 #[derive(Debug, Clone)]
 pub enum Band {
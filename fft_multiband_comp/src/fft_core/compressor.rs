@@ -1,4 +1,4 @@
-use crate::utils;
+use spectral_core::utils;
 use nih_plug::nih_log;
 
 #[derive(Clone, Copy)]
@@ -28,6 +28,11 @@ impl Compressor {
         }
     }
 
+    pub fn reset(&mut self) {
+        self.env = -100.0;
+        self.reduced = 0.0;
+    }
+
     pub fn set_params(&mut self, th: f32, r: f32, w: f32, att_coeff: f32, rel_coeff: f32) {
         self.th = th;
         self.r = r;
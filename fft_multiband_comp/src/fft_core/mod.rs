@@ -1,6 +1,3 @@
-pub mod stereo_fft_processor;
-pub mod fft_data;
-pub mod fft_size;
 pub mod compressor;
 pub mod env_follower;
 pub mod spectral_multiband_compressor;
\ No newline at end of file
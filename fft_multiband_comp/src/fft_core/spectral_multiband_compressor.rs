@@ -1,15 +1,28 @@
+use std::ops::Range;
+
 use realfft::{num_complex::Complex, num_traits::Zero};
+use spectral_core::{
+    analyzer::AnalyzerLayers,
+    fft_data::{self, FFTData},
+    fft_size::FFTSize,
+    resolution::{BandSize, MAX_BANDS, MAX_EFFECT_BANDS},
+    smoothing::HopSmoother,
+    spectral_effect::{SpectralEffect, SpectrumValues},
+    utils,
+};
 
-use crate::utils;
+use crate::analyzer_data::{AnalyzerChannel, AnalyzerData};
 
-use super::{compressor::Compressor, fft_size::FFTSize};
+use super::compressor::Compressor;
 
 pub struct SpectralMultibandCompressor {
-    compressors: [Vec<Compressor>; 2],
+    // one compressor per bin for every channel, for each band of the processor's `Resolution` and
+    // of the outgoing engine while it crossfades
+    compressors: Vec<Vec<Vec<Compressor>>>,
+    bands: Vec<BandSize>,
+    band: usize,
     pub low_mid_freq: f32,
     pub mid_high_freq: f32,
-    pub low_mid_idx: usize,
-    pub mid_high_idx: usize,
 
     pub lpf: utils::SimpleLPF,
 
@@ -26,13 +39,18 @@ pub struct SpectralMultibandCompressor {
     pub high_ratio: f32,
     pub high_up_ratio: f32,
     pub high_gain: f32,
+    // only the processed signal goes through these, so the dry side of the mix and the bypass keep
+    // the input's level
+    pub in_gain: f32,
+    pub out_gain: f32,
+    // the thresholds and gains above are the smoothed values of the current hop
+    threshold_smoothers: [HopSmoother; 3],
+    gain_smoothers: [HopSmoother; 3],
+    in_out_gain_smoothers: [HopSmoother; 2],
 
     pub attack_ms: f32,
     pub release_ms: f32,
 
-    pub mix: f32,
-
-    pub hops_per_second: f32,
     pub fft_size: usize,
 
     pub delta: Vec<f32>,
@@ -41,6 +59,10 @@ pub struct SpectralMultibandCompressor {
     pub smooth: f32,
 
     pub sample_rate: f32,
+
+    analyzer_input_data: triple_buffer::Input<AnalyzerData>,
+    analyzer_channel: AnalyzerChannel,
+    pub analyzer_layers: AnalyzerLayers,
 }
 
 impl SpectralMultibandCompressor {
@@ -58,8 +80,9 @@ impl SpectralMultibandCompressor {
         low_mid_freq: f32,
         mid_high_freq: f32,
         sample_rate: f32,
+        analyzer_buffer: triple_buffer::Input<AnalyzerData>,
     ) -> Self {
-        let mut compressors = Vec::with_capacity(fft_size / 2 + 1);
+        let mut compressors = Vec::with_capacity(FFTSize::MAX.num_bins());
         let attack_coeff = (-1.0 / (attack_ms * hops_per_second * 0.001)).exp();
         let release_coeff = (-1.0 / (release_ms * hops_per_second * 0.001)).exp();
         for _ in 0..(fft_size / 2 + 1) {
@@ -67,11 +90,16 @@ impl SpectralMultibandCompressor {
             compressors.push(compressor);
         }
 
-        let low_mid_idx = utils::freq_to_bin(low_mid_freq, fft_size, sample_rate);
-        let mid_high_idx = utils::freq_to_bin(mid_high_freq, fft_size, sample_rate);
+        let mut bands = Vec::with_capacity(MAX_EFFECT_BANDS);
+        bands.push(BandSize {
+            transform_size: fft_size,
+            hop_size: (sample_rate / hops_per_second) as usize,
+        });
 
         Self {
-            compressors: [compressors.to_vec(), compressors.to_vec()],
+            compressors: (0..MAX_EFFECT_BANDS).map(|_| vec![compressors_like(&compressors)]).collect(),
+            bands,
+            band: 0,
             lpf: utils::SimpleLPF::new(0.001f32),
             low_threshold,
             low_ratio: 2.0,
@@ -85,41 +113,29 @@ impl SpectralMultibandCompressor {
             high_ratio: 2.0,
             high_up_ratio: 5.0,
             high_gain,
+            threshold_smoothers: [low_threshold, mid_threshold, high_threshold].map(HopSmoother::new),
+            in_gain: 1.0,
+            out_gain: 1.0,
+            gain_smoothers: [low_gain, mid_gain, high_gain].map(HopSmoother::new),
+            in_out_gain_smoothers: [1.0, 1.0].map(HopSmoother::new),
             attack_ms,
             release_ms,
-            hops_per_second,
             fft_size,
             low_mid_freq,
             mid_high_freq,
-            low_mid_idx,
-            mid_high_idx,
-            mix: 0.0,
             sample_rate,
-            delta: vec![0.0f32; fft_size / 2 + 1],
+            delta: utils::bins_vec(fft_size / 2 + 1, 0.0f32),
             curve_compressor: Compressor::new(low_threshold, 2.0, 20.0, 0.0, 0.0),
             smooth: 0.00f32,
+            analyzer_input_data: analyzer_buffer,
+            analyzer_channel: AnalyzerChannel::Merged,
+            analyzer_layers: AnalyzerLayers::new(),
         }
     }
 
-    pub fn resize(&mut self, fft_size: usize) {
-        self.fft_size = fft_size;
-        let bin_num = fft_size / 2 + 1;
-
-        let attack_coeff = (-1.0 / (self.attack_ms * self.hops_per_second * 0.001)).exp();
-        let release_coeff = (-1.0 / (self.release_ms * self.hops_per_second * 0.001)).exp();
-        self.delta.resize(bin_num, 0.0f32);
-        self.compressors[0].resize(
-            bin_num,
-            Compressor::new(self.low_threshold, 0.2, 5.0, attack_coeff, release_coeff),
-        );
-        self.compressors[1].resize(
-            bin_num,
-            Compressor::new(self.low_threshold, 2.0, 5.0, attack_coeff, release_coeff),
-        );
-    }
-
     pub fn set_params(
         &mut self,
+        an_chan: AnalyzerChannel,
         low_threshold: f32,
         low_ratio: f32,
         low_up_ratio: f32,
@@ -134,12 +150,11 @@ impl SpectralMultibandCompressor {
         high_gain: f32,
         attack_ms: f32,
         release_ms: f32,
-        hops_per_second: f32,
-        mix: f32,
         low_mid_freq: f32,
         mid_high_freq: f32,
         smooth: f32,
     ) {
+        self.analyzer_channel = an_chan;
         let changed = self.low_mid_freq != low_mid_freq || self.mid_high_freq != mid_high_freq;
         if low_mid_freq > mid_high_freq {
             let temp = low_mid_freq;
@@ -150,155 +165,287 @@ impl SpectralMultibandCompressor {
             self.mid_high_freq = mid_high_freq;
         }
 
-        self.low_mid_idx = utils::freq_to_bin(self.low_mid_freq, self.fft_size, self.sample_rate);
-        self.mid_high_idx = utils::freq_to_bin(self.mid_high_freq, self.fft_size, self.sample_rate);
+        let (low, mid, high) = self.freq_ranges();
 
         // check if any parameters changed, if so update only the compressors in said band
-        if self.low_threshold != low_threshold || changed {
-            for i in 0..self.low_mid_idx {
-                self.compressors[0][i].th = low_threshold;
-                self.compressors[1][i].th = low_threshold;
-            }
+        // the thresholds ramp in per hop, only moved bins need the current ones
+        if changed {
+            let th = self.low_threshold;
+            self.update_compressors(low.clone(), |compressor| compressor.th = th);
         }
 
         if self.low_ratio != low_ratio || changed {
-            for i in 0..self.low_mid_idx {
-                self.compressors[0][i].r = low_ratio;
-                self.compressors[1][i].r = low_ratio;
-            }
+            self.update_compressors(low.clone(), |compressor| compressor.r = low_ratio);
         }
 
         if self.low_up_ratio != low_up_ratio || changed {
-            for i in 0..self.low_mid_idx {
-                self.compressors[0][i].up_r = low_up_ratio;
-                self.compressors[1][i].up_r = low_up_ratio;
-            }
+            self.update_compressors(low, |compressor| compressor.up_r = low_up_ratio);
         }
 
-        if self.mid_threshold != mid_threshold || changed {
-            for i in self.low_mid_idx..self.mid_high_idx {
-                self.compressors[0][i].th = mid_threshold;
-                self.compressors[1][i].th = mid_threshold;
-            }
+        if changed {
+            let th = self.mid_threshold;
+            self.update_compressors(mid.clone(), |compressor| compressor.th = th);
         }
 
         if self.mid_ratio != mid_ratio || changed {
-            for i in self.low_mid_idx..self.mid_high_idx {
-                self.compressors[0][i].r = mid_ratio;
-                self.compressors[1][i].r = mid_ratio;
-            }
+            self.update_compressors(mid.clone(), |compressor| compressor.r = mid_ratio);
         }
 
         if self.mid_up_ratio != mid_up_ratio || changed {
-            for i in self.low_mid_idx..self.mid_high_idx {
-                self.compressors[0][i].up_r = mid_up_ratio;
-                self.compressors[1][i].up_r = mid_up_ratio;
-            }
+            self.update_compressors(mid, |compressor| compressor.up_r = mid_up_ratio);
         }
 
-        if self.high_threshold != high_threshold || changed {
-            for i in self.mid_high_idx..(self.fft_size / 2 + 1) {
-                self.compressors[0][i].th = high_threshold;
-                self.compressors[1][i].th = high_threshold;
-            }
+        if changed {
+            let th = self.high_threshold;
+            self.update_compressors(high.clone(), |compressor| compressor.th = th);
         }
 
         if self.high_ratio != high_ratio || changed {
-            for i in self.mid_high_idx..(self.fft_size / 2 + 1) {
-                self.compressors[0][i].r = high_ratio;
-                self.compressors[1][i].r = high_ratio;
-            }
+            self.update_compressors(high.clone(), |compressor| compressor.r = high_ratio);
         }
 
         if self.high_up_ratio != high_up_ratio || changed {
-            for i in self.mid_high_idx..(self.fft_size / 2 + 1) {
-                self.compressors[0][i].up_r = high_up_ratio;
-                self.compressors[1][i].up_r = high_up_ratio;
-            }
+            self.update_compressors(high, |compressor| compressor.up_r = high_up_ratio);
         }
 
-        if self.attack_ms != attack_ms || self.release_ms != release_ms || changed {
-            let attack_coeff = (-1.0 / (attack_ms * hops_per_second * 0.001)).exp();
-            let release_coeff = (-1.0 / (release_ms * hops_per_second * 0.001)).exp();
-            for i in 0..(self.fft_size / 2 + 1) {
-                self.compressors[0][i].att = attack_coeff;
-                self.compressors[0][i].rel = release_coeff;
-                self.compressors[1][i].att = attack_coeff;
-                self.compressors[1][i].rel = release_coeff;
-            }
+        if self.attack_ms != attack_ms || self.release_ms != release_ms {
+            self.attack_ms = attack_ms;
+            self.release_ms = release_ms;
+            self.update_time_constants();
         }
 
         self.lpf.set_a(smooth);
         self.smooth = smooth;
 
-        self.low_threshold = low_threshold;
+        for (smoother, target) in self.threshold_smoothers.iter_mut().zip([low_threshold, mid_threshold, high_threshold]) {
+            smoother.set_target(target);
+        }
+        for (smoother, target) in self.gain_smoothers.iter_mut().zip([low_gain, mid_gain, high_gain]) {
+            smoother.set_target(target);
+        }
+
         self.low_ratio = low_ratio;
         self.low_up_ratio = low_up_ratio;
-        self.low_gain = low_gain;
-        self.mid_threshold = mid_threshold;
         self.mid_ratio = mid_ratio;
         self.mid_up_ratio = mid_up_ratio;
-        self.mid_gain = mid_gain;
-        self.high_threshold = high_threshold;
         self.high_ratio = high_ratio;
         self.high_up_ratio = high_up_ratio;
-        self.high_gain = high_gain;
-        self.attack_ms = attack_ms;
-        self.release_ms = release_ms;
-        self.hops_per_second = hops_per_second;
-        self.mix = mix;
     }
 
-    pub fn set_hops_per_second(&mut self, hops_per_second: f32) {
-        self.hops_per_second = hops_per_second;
-        let attack_coeff = (-1.0 / (self.attack_ms * hops_per_second * 0.001)).exp();
-        let release_coeff = (-1.0 / (self.release_ms * hops_per_second * 0.001)).exp();
-        for i in 0..(self.fft_size / 2 + 1) {
-            self.compressors[0][i].att = attack_coeff;
-            self.compressors[0][i].rel = release_coeff;
-            self.compressors[1][i].att = attack_coeff;
-            self.compressors[1][i].rel = release_coeff;
+    /// Linear gains of the processed signal, `in_gain` before the compressors and `out_gain` after
+    /// them.
+    pub fn set_in_out_gains(&mut self, in_gain: f32, out_gain: f32) {
+        for (smoother, target) in self.in_out_gain_smoothers.iter_mut().zip([in_gain, out_gain]) {
+            smoother.set_target(target);
+        }
+    }
+
+    /// Moves the thresholds and gains one hop further towards their targets. Only the compressors
+    /// of bands whose threshold actually moved are touched.
+    fn next_hop(&mut self) {
+        let (low, mid, high) = self.freq_ranges();
+        let thresholds = [self.low_threshold, self.mid_threshold, self.high_threshold];
+        let next_thresholds = self.threshold_smoothers.each_mut().map(|smoother| smoother.next_hop());
+        for ((freqs, th), next_th) in [low, mid, high].into_iter().zip(thresholds).zip(next_thresholds) {
+            if next_th != th {
+                self.update_compressors(freqs, |compressor| compressor.th = next_th);
+            }
+        }
+        [self.low_threshold, self.mid_threshold, self.high_threshold] = next_thresholds;
+        [self.low_gain, self.mid_gain, self.high_gain] = self.gain_smoothers.each_mut().map(|smoother| smoother.next_hop());
+        [self.in_gain, self.out_gain] = self.in_out_gain_smoothers.each_mut().map(|smoother| smoother.next_hop());
+    }
+
+    /// Frequency ranges of the low, mid and high compressor bands.
+    fn freq_ranges(&self) -> (Range<f32>, Range<f32>, Range<f32>) {
+        (0.0..self.low_mid_freq, self.low_mid_freq..self.mid_high_freq, self.mid_high_freq..f32::INFINITY)
+    }
+
+    /// Runs `f` on the compressors of the bins within `freqs`, in every channel of every band. The
+    /// bands have different bin spacings, so the same frequencies are different bins in each.
+    fn update_compressors(&mut self, freqs: Range<f32>, f: impl Fn(&mut Compressor)) {
+        let sample_rate = self.sample_rate;
+        for (band, compressors) in self.used_bands() {
+            let num_bins = utils::fft_size_to_bins(band.transform_size);
+            let to_bin = |freq: f32| utils::freq_to_bin(freq, band.transform_size, sample_rate).min(num_bins);
+            let bins = to_bin(freqs.start)..to_bin(freqs.end);
+            for compressors in compressors.iter_mut() {
+                compressors[bins.clone()].iter_mut().for_each(&f);
+            }
         }
     }
 
-    pub fn process(
+    /// Attack and release are per hop, so they depend on each band's hop size.
+    fn update_time_constants(&mut self) {
+        let (sample_rate, attack_ms, release_ms) = (self.sample_rate, self.attack_ms, self.release_ms);
+        for (band, compressors) in self.used_bands() {
+            let hops_per_second = sample_rate / band.hop_size as f32;
+            let attack_coeff = (-1.0 / (attack_ms * hops_per_second * 0.001)).exp();
+            let release_coeff = (-1.0 / (release_ms * hops_per_second * 0.001)).exp();
+            for compressor in compressors.iter_mut().flatten() {
+                compressor.att = attack_coeff;
+                compressor.rel = release_coeff;
+            }
+        }
+    }
+
+    /// The bands from `set_bands` with their compressors, without the unused ones between the
+    /// incoming and the outgoing engine's bands.
+    fn used_bands(&mut self) -> impl Iterator<Item = (&BandSize, &mut Vec<Vec<Compressor>>)> {
+        self.bands
+            .iter()
+            .zip(self.compressors.iter_mut())
+            .filter(|(band, _)| band.transform_size > 0)
+    }
+
+    /// Applies every parameter to every compressor, after the bands or the sample rate changed.
+    fn update_all_compressors(&mut self) {
+        let (low, mid, high) = self.freq_ranges();
+        let settings = [
+            (low, self.low_threshold, self.low_ratio, self.low_up_ratio),
+            (mid, self.mid_threshold, self.mid_ratio, self.mid_up_ratio),
+            (high, self.high_threshold, self.high_ratio, self.high_up_ratio),
+        ];
+        for (freqs, th, r, up_r) in settings {
+            self.update_compressors(freqs, |compressor| {
+                compressor.th = th;
+                compressor.r = r;
+                compressor.up_r = up_r;
+            });
+        }
+        self.update_time_constants();
+    }
+}
+
+/// A copy of `compressors` with room for every bin of the largest `FFTSize`.
+fn compressors_like(compressors: &[Compressor]) -> Vec<Compressor> {
+    let mut copy = Vec::with_capacity(FFTSize::MAX.num_bins());
+    copy.extend_from_slice(compressors);
+    copy
+}
+
+impl SpectralEffect for SpectralMultibandCompressor {
+    fn set_num_channels(&mut self, num_channels: usize) {
+        // new channels start out with the same settings as the first one
+        for band in self.compressors.iter_mut() {
+            band.truncate(num_channels);
+            while band.len() < num_channels {
+                let compressors = compressors_like(&band[0]);
+                band.push(compressors);
+            }
+        }
+    }
+
+    fn resize(&mut self, fft_size: usize) {
+        self.fft_size = fft_size;
+        let bin_num = fft_size / 2 + 1;
+
+        // the bins are only set up properly by `set_bands`, which comes right after this
+        self.delta.resize(bin_num, 0.0f32);
+        for compressors in self.compressors.iter_mut().flatten() {
+            compressors.resize(bin_num, Compressor::new(self.low_threshold, 2.0, 5.0, 0.0, 0.0));
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.analyzer_layers.set_hop_rate(sample_rate, hop_size);
+        self.sample_rate = sample_rate as f32;
+        let smoothers = self.threshold_smoothers.iter_mut().chain(self.gain_smoothers.iter_mut());
+        for smoother in smoothers.chain(self.in_out_gain_smoothers.iter_mut()) {
+            smoother.set_hop_rate(sample_rate, hop_size);
+        }
+        self.update_all_compressors();
+    }
+
+    fn set_bands(&mut self, bands: &[BandSize]) {
+        self.bands.clear();
+        self.bands.extend_from_slice(bands);
+        self.update_all_compressors();
+    }
+
+    fn set_band(&mut self, band: usize) {
+        self.band = band;
+    }
+
+    // the outgoing engine keeps the envelopes it has, the incoming one starts over
+    fn crossfade_started(&mut self) {
+        for band in 0..MAX_BANDS {
+            self.compressors.swap(band, MAX_BANDS + band);
+        }
+        for compressor in self.compressors[..MAX_BANDS].iter_mut().flatten().flatten() {
+            compressor.reset();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.analyzer_layers.reset();
+        for compressor in self.compressors.iter_mut().flatten().flatten() {
+            compressor.reset();
+        }
+        self.delta.fill(0.0);
+        self.lpf.reset();
+        // the thresholds and gains jump to their targets with the next hop
+        let smoothers = self.threshold_smoothers.iter_mut().chain(self.gain_smoothers.iter_mut());
+        for smoother in smoothers.chain(self.in_out_gain_smoothers.iter_mut()) {
+            smoother.reset();
+        }
+    }
+
+    // the gain reduction is applied to the complex bins, the phase isn't needed
+    fn spectrum_values(&self) -> SpectrumValues {
+        SpectrumValues {
+            magnitude: true,
+            db: true,
+            ..SpectrumValues::NONE
+        }
+    }
+
+    fn process_spectrum(
         &mut self,
-        mag: [&Vec<f32>; 2],
-        phase: [&Vec<f32>; 2],
-        db: [&Vec<f32>; 2],
-        freq: [&Vec<f32>; 2],
-        output_buffer: &mut [Vec<Complex<f32>>; 2],
+        input: &[FFTData],
+        _aux: Option<&[FFTData]>,
+        output_buffer: &mut [Vec<Complex<f32>>],
     ) {
+        // the thresholds and gains move once per hop of the main band
+        if self.band == 0 {
+            self.next_hop();
+        }
+
+        let num_channels = input.len();
+        // the spectra can be smaller than `delta` while crossfading or with several bands
+        let len = input[0].spectrum_db.len();
+
         for d in self.delta.iter_mut() {
             *d = 0.0f32;
         }
-        for channel in 0..2 {
-            for (i, db) in db[channel].iter().enumerate() {
-                let gain = match freq[channel][i] {
+        for channel in 0..num_channels {
+            let bins = &input[channel].fft_out;
+            let db = &input[channel].spectrum_db;
+            let freq = &input[channel].spectrum_freq;
+            for (i, db) in db.iter().enumerate() {
+                let gain = match freq[i] {
                     f if f < self.low_mid_freq => self.low_gain,
                     f if f < self.mid_high_freq => self.mid_gain,
                     _ => self.high_gain,
                 };
-                let gained_input = utils::gain_to_db(utils::db_to_gain(*db) * gain); //dB
-                let delta: f32 = self.compressors[channel][i].process_db(gained_input); //dB
+                let gained_input = utils::gain_to_db(utils::db_to_gain(*db) * gain * self.in_gain); //dB
+                let delta: f32 = self.compressors[self.band][channel][i].process_db(gained_input); //dB
 
                 //let output = mag[channel][i] * utils::db_to_gain(delta); // linear
                 //output_buffer[channel][i] = Complex::from_polar(utils::lerp(utils::db_to_gain(*db), output, self.mix), phase[channel][i]);
-                // this will average over both channels
-                self.delta[i] += delta / 2f32;
+                // this will average over all channels
+                self.delta[i] += delta / num_channels as f32;
             }
             self.lpf.set_a(self.smooth);
             // smoth out the delta
-            let len = self.delta.len();
             self.lpf.calculate_a_range();
-            for (i, delta) in self.delta.iter_mut().enumerate().skip(1).take(len - 2) {
+            for (i, delta) in self.delta[..len].iter_mut().enumerate().skip(1).take(len - 2) {
                 self.lpf.set_a_log_scale(i, len);
                 *delta = self.lpf.process(*delta);
             }
 
             self.lpf.set_a(self.smooth);
-            for (i, delta) in self
-                .delta
+            for (i, delta) in self.delta[..len]
                 .iter_mut()
                 .enumerate()
                 .rev()
@@ -309,38 +456,64 @@ impl SpectralMultibandCompressor {
                 *delta = self.lpf.process(*delta);
             }
 
-            for (i, delta) in self.delta.iter().enumerate() {
-                let output = mag[channel][i] * utils::db_to_gain(*delta); // mag * delta as linear
-                output_buffer[channel][i] = Complex::from_polar(
-                    utils::lerp(utils::db_to_gain(db[channel][i]), output, self.mix),
-                    phase[channel][i],
-                );
+            let in_out_gain = self.in_gain * self.out_gain;
+            for (i, delta) in self.delta[..len].iter().enumerate() {
+                output_buffer[channel][i] = bins[i] * (utils::db_to_gain(*delta) * in_out_gain); // bin * delta as linear
             }
             self.delta[0] = 0.0;
-            self.delta[db[0].len() - 1] = 0.0f32;
+            self.delta[db.len() - 1] = 0.0f32;
             output_buffer[channel][0] = Complex::zero();
-            output_buffer[channel][db[0].len() - 1] = Complex::zero();
+            output_buffer[channel][db.len() - 1] = Complex::zero();
         }
     }
 
-    pub fn get_curve(&mut self, vec: &mut Vec<f32>, band: usize) {
-        if band == 0 {
-            self.curve_compressor.r = self.low_ratio;
-            self.curve_compressor.th = self.low_threshold;
-            self.curve_compressor.up_r = self.low_up_ratio;
-            self.curve_compressor.get_curve(vec);
-        }
-        if band == 1 {
-            self.curve_compressor.r = self.mid_ratio;
-            self.curve_compressor.th = self.mid_threshold;
-            self.curve_compressor.up_r = self.mid_up_ratio;
-            self.curve_compressor.get_curve(vec);
-        }
-        if band == 2 {
-            self.curve_compressor.r = self.high_ratio;
-            self.curve_compressor.th = self.high_threshold;
-            self.curve_compressor.up_r = self.high_up_ratio;
-            self.curve_compressor.get_curve(vec);
+    fn analyze(&mut self, output: &[FFTData], _aux: Option<&[FFTData]>, fft_size: usize) {
+        let num_bins = utils::fft_size_to_bins(fft_size);
+        let analyzer_input = self.analyzer_input_data.input_buffer();
+        analyzer_input.spectra.magnitudes.fill(0.0f32);
+        analyzer_input.spectra.num_bins = num_bins;
+
+        // mono only has a left channel
+        let right = output.get(1).unwrap_or(&output[0]);
+        for (i, mag) in analyzer_input.spectra.magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].spectrum_db[i],
+                AnalyzerChannel::Right => right.spectrum_db[i],
+                AnalyzerChannel::Merged => fft_data::average_db(output, i),
+            };
+        }
+        for (i, mag) in analyzer_input.spectra.input_magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].analyzer_input_db[i],
+                AnalyzerChannel::Right => right.analyzer_input_db[i],
+                AnalyzerChannel::Merged => fft_data::average_input_db(output, i),
+            };
+        }
+
+        analyzer_input.thresholds = [self.low_threshold, self.mid_threshold, self.high_threshold];
+        analyzer_input.freq_bands = [self.low_mid_freq, self.mid_high_freq];
+
+        let curves = [
+            (&mut analyzer_input.comp_curve_low, self.low_threshold, self.low_ratio, self.low_up_ratio),
+            (&mut analyzer_input.comp_curve_mid, self.mid_threshold, self.mid_ratio, self.mid_up_ratio),
+            (&mut analyzer_input.comp_curve_high, self.high_threshold, self.high_ratio, self.high_up_ratio),
+        ];
+        for (curve, th, r, up_r) in curves {
+            self.curve_compressor.th = th;
+            self.curve_compressor.r = r;
+            self.curve_compressor.up_r = up_r;
+            self.curve_compressor.get_curve(curve);
         }
+
+        for (i, delta) in analyzer_input.delta[0..num_bins].iter_mut().enumerate() {
+            *delta = self.delta[i];
+        }
+
+        for (i, f) in analyzer_input.frequencies[0..num_bins].iter_mut().enumerate() {
+            *f = output[0].spectrum_freq[i];
+        }
+
+        self.analyzer_layers.process_spectra(&mut analyzer_input.spectra, false);
+        self.analyzer_input_data.publish();
     }
 }
@@ -1,25 +1,90 @@
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 
 use formatters::{s2v_compression_ratio, s2v_f32_percentage, v2s_compression_ratio, v2s_f32_percentage};
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
 
-use crate::{analyzer_data::AnalyzerChannel, editor, fft_core::fft_size::FFTSize, utils};
+use crate::{analyzer_data::AnalyzerChannel, editor};
+use spectral_core::{analyzer::{AnalyzerMode, MAX_RMS_FRAMES}, analyzer_axes::AnalyzerAxes, channel_mode::ChannelMode, fft_size::FFTSize, overlap::Overlap, resolution::{self, Resolution}, spectrogram::{self, Colormap}, transient, utils, window::WindowType, zero_padding::ZeroPadding};
 
 #[derive(Params)]
 pub struct PluginParams {
     #[persist = "editor-state"]
     pub editor_state: Arc<ViziaState>,
 
+    // the analyzer's zoomed and panned ranges, see `AnalyzerAxes::to_array`
+    #[persist = "analyzer-axes"]
+    pub analyzer_axes: Mutex<[f32; 4]>,
+
     #[id = "fft-size"]
     pub fft_size: EnumParam<FFTSize>,
 
+    #[id = "overlap"]
+    pub overlap: EnumParam<Overlap>,
+
+    #[id = "zero-padding"]
+    pub zero_padding: EnumParam<ZeroPadding>,
+
+    #[id = "resolution"]
+    pub resolution: EnumParam<Resolution>,
+
+    #[id = "low-crossover"]
+    pub low_crossover: FloatParam,
+
+    #[id = "high-crossover"]
+    pub high_crossover: FloatParam,
+
+    #[id = "transient-switching"]
+    pub transient_switching: BoolParam,
+
+    #[id = "transient-threshold"]
+    pub transient_threshold: FloatParam,
+
+    #[id = "window-type"]
+    pub window_type: EnumParam<WindowType>,
+
+    #[id = "kaiser-beta"]
+    pub kaiser_beta: FloatParam,
+
+    #[id = "channel-mode"]
+    pub channel_mode: EnumParam<ChannelMode>,
+
     #[id = "analyzer-channel"]
     pub analyzer_channel: EnumParam<AnalyzerChannel>,
 
+    #[id = "analyzer-mode"]
+    pub analyzer_mode: EnumParam<AnalyzerMode>,
+
+    #[id = "analyzer-time"]
+    pub analyzer_time: FloatParam,
+
+    #[id = "analyzer-rms-frames"]
+    pub analyzer_rms_frames: IntParam,
+
+    #[id = "analyzer-peak-hold"]
+    pub analyzer_peak_hold: BoolParam,
+
+    #[id = "analyzer-peak-decay"]
+    pub analyzer_peak_decay: FloatParam,
+
+    #[id = "analyzer-show-input"]
+    pub analyzer_show_input: BoolParam,
+
+    #[id = "analyzer-show-output"]
+    pub analyzer_show_output: BoolParam,
+
+    #[id = "spectrogram-colormap"]
+    pub spectrogram_colormap: EnumParam<Colormap>,
+
+    #[id = "spectrogram-min-db"]
+    pub spectrogram_min_db: FloatParam,
+
+    #[id = "spectrogram-max-db"]
+    pub spectrogram_max_db: FloatParam,
+
     #[id = "low-mid-frequency"]
     pub low_mid_frequency: FloatParam,
 
@@ -80,18 +145,91 @@ pub struct PluginParams {
     #[id = "mix"]
     pub mix: FloatParam,
 
+    #[id = "bypass"]
+    pub bypass: BoolParam,
+
     #[id = "smooth"]
     pub smooth: FloatParam,
 }
 
 impl PluginParams {
     pub fn new(size_callback: Arc<AtomicBool>) -> Self {
+        let overlap_callback = size_callback.clone();
+        let zero_padding_callback = size_callback.clone();
+        let resolution_callback = size_callback.clone();
         Self {
             editor_state: editor::default_state(),
+            analyzer_axes: Mutex::new(AnalyzerAxes::default().to_array()),
             fft_size: EnumParam::new("FFT Size", FFTSize::_1024).with_callback(Arc::new(
                 move |_| size_callback.store(true, Ordering::Release),
             )),
+            overlap: EnumParam::new("Overlap", Overlap::_4).with_callback(Arc::new(
+                move |_| overlap_callback.store(true, Ordering::Release),
+            )),
+            zero_padding: EnumParam::new("Zero Padding", ZeroPadding::_1).with_callback(Arc::new(
+                move |_| zero_padding_callback.store(true, Ordering::Release),
+            )),
+            resolution: EnumParam::new("Resolution", Resolution::Single).with_callback(Arc::new(
+                move |_| resolution_callback.store(true, Ordering::Release),
+            )),
+            low_crossover: FloatParam::new(
+                "Low Crossover",
+                resolution::DEFAULT_LOW_CROSSOVER_HZ,
+                FloatRange::Skewed { min: 50.0, max: 2_000.0, factor: 0.6 },
+            )
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(1)),
+            high_crossover: FloatParam::new(
+                "High Crossover",
+                resolution::DEFAULT_HIGH_CROSSOVER_HZ,
+                FloatRange::Skewed { min: 1_000.0, max: 16_000.0, factor: 0.6 },
+            )
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(1)),
+            transient_switching: BoolParam::new("Transient Switching", false),
+            transient_threshold: FloatParam::new(
+                "Transient Threshold",
+                transient::DEFAULT_THRESHOLD_DB,
+                FloatRange::Linear { min: 3.0, max: 24.0 },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            window_type: EnumParam::new("Window", WindowType::Hann),
+            kaiser_beta: FloatParam::new("Kaiser Beta", 8.0, FloatRange::Linear { min: 0.0, max: 20.0 }),
+            channel_mode: EnumParam::new("Channel Mode", ChannelMode::Stereo),
             analyzer_channel: EnumParam::new("Analyzer Channel", AnalyzerChannel::Merged),
+            analyzer_mode: EnumParam::new("Analyzer Mode", AnalyzerMode::Average),
+            analyzer_time: FloatParam::new(
+                "Analyzer Time",
+                100.0,
+                FloatRange::Skewed { min: 10.0, max: 2_000.0, factor: FloatRange::skew_factor(-2.0) },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            analyzer_rms_frames: IntParam::new("Analyzer RMS Frames", 8, IntRange::Linear { min: 1, max: MAX_RMS_FRAMES as i32 }),
+            analyzer_peak_hold: BoolParam::new("Analyzer Peak Hold", true),
+            analyzer_peak_decay: FloatParam::new(
+                "Analyzer Peak Decay",
+                20.0,
+                FloatRange::Skewed { min: 1.0, max: 120.0, factor: FloatRange::skew_factor(-1.0) },
+            )
+            .with_unit(" dB/s")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            analyzer_show_input: BoolParam::new("Analyzer Input", true),
+            analyzer_show_output: BoolParam::new("Analyzer Output", true),
+            spectrogram_colormap: EnumParam::new("Spectrogram Colormap", Colormap::Magma),
+            spectrogram_min_db: FloatParam::new(
+                "Spectrogram Floor",
+                spectrogram::DEFAULT_MIN_DB,
+                FloatRange::Linear { min: -150.0, max: -30.0 },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            spectrogram_max_db: FloatParam::new(
+                "Spectrogram Ceiling",
+                spectrogram::DEFAULT_MAX_DB,
+                FloatRange::Linear { min: -60.0, max: 20.0 },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
             
             low_mid_frequency: FloatParam::new(
                 "Low/Mid Frequency", 
@@ -231,6 +369,7 @@ impl PluginParams {
             .with_string_to_value(s2v_f32_percentage())
             .with_value_to_string(v2s_f32_percentage(2))
             .with_unit("%"),
+            bypass: BoolParam::new("Bypass", false).make_bypass(),
             low_up_ratio: FloatParam::new("Low Up Ratio", 1.0,  FloatRange::Linear { min: 1.0, max: 5.0 })
             .with_string_to_value(s2v_compression_ratio())
             .with_value_to_string(v2s_compression_ratio(2)),
@@ -1,6 +1,7 @@
 use realfft::num_complex::Complex;
+use spectral_core::{analyzer::AnalyzerLayers, fft_data::{self, FFTData}, smoothing::HopSmoother, spectral_effect::{SpectralEffect, SpectrumValues}, utils};
 
-use crate::utils;
+use crate::analyzer_data::{AnalyzerChannel, AnalyzerData};
 use nih_plug::nih_log;
 
 pub struct Peacemaker {
@@ -9,60 +10,115 @@ pub struct Peacemaker {
     pub highcut: f32,
     stereo_link: bool,
     pub reduction: Vec<f32>,
+
+    // the parameters above are the smoothed values of the current hop
+    sidechain_gain_smoother: HopSmoother,
+    lowcut_smoother: HopSmoother,
+    highcut_smoother: HopSmoother,
+    band: usize,
+
+    analyzer_input_data: triple_buffer::Input<AnalyzerData>,
+    analyzer_channel: AnalyzerChannel,
+    pub analyzer_layers: AnalyzerLayers,
 }
 
 impl Peacemaker {
-    pub fn new(num_bins: usize) -> Self {
+    pub fn new(num_bins: usize, analyzer_buffer: triple_buffer::Input<AnalyzerData>) -> Self {
         Self {
             sidechain_gain: 0.0,
             lowcut: 20.0,
             highcut: 20_000.0,
             stereo_link: false,
-            reduction: vec![0.0f32; num_bins],
+            reduction: utils::bins_vec(num_bins, 0.0f32),
+            sidechain_gain_smoother: HopSmoother::new(0.0),
+            lowcut_smoother: HopSmoother::new(20.0),
+            highcut_smoother: HopSmoother::new(20_000.0),
+            band: 0,
+            analyzer_input_data: analyzer_buffer,
+            analyzer_channel: AnalyzerChannel::Merged,
+            analyzer_layers: AnalyzerLayers::new(),
         }
     }
 
-    pub fn resize(&mut self, new_bin_size: usize) {
-        self.reduction.resize(new_bin_size, 0.0f32);
+    pub fn set_params(&mut self, an_chan: AnalyzerChannel, side_gain: f32, low: f32, high: f32, s_link: bool) {
+        self.analyzer_channel = an_chan;
+        self.sidechain_gain_smoother.set_target(side_gain);
+        self.lowcut_smoother.set_target(low);
+        self.highcut_smoother.set_target(high);
+        self.stereo_link = s_link;
+    }
+}
+
+impl SpectralEffect for Peacemaker {
+    fn resize(&mut self, fft_size: usize) {
+        self.reduction.resize(utils::fft_size_to_bins(fft_size), 0.0f32);
     }
 
-    pub fn set_params(&mut self, side_gain: f32, low: f32, high: f32, s_link: bool) {
-        self.sidechain_gain = side_gain;
-        self.lowcut = low;
-        self.highcut = high;
-        self.stereo_link = s_link;
-    }   
-
-    pub fn process_spectrum(&mut self, 
-        mag: [&Vec<f32>; 2], 
-        phase: [&Vec<f32>; 2], 
-        db: [&Vec<f32>; 2], 
-        freq: [&Vec<f32>; 2], 
-        aux_db: [&Vec<f32>; 2],
-        aux_mag: [&Vec<f32>; 2],
-        output_buffer: &mut [Vec<Complex<f32>>; 2]) 
-    {
+    fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.analyzer_layers.set_hop_rate(sample_rate, hop_size);
+        self.sidechain_gain_smoother.set_hop_rate(sample_rate, hop_size);
+        self.lowcut_smoother.set_hop_rate(sample_rate, hop_size);
+        self.highcut_smoother.set_hop_rate(sample_rate, hop_size);
+    }
+
+    fn set_band(&mut self, band: usize) {
+        self.band = band;
+    }
+
+    fn reset(&mut self) {
+        self.analyzer_layers.reset();
+        self.reduction.fill(0.0);
+        for smoother in [&mut self.sidechain_gain_smoother, &mut self.lowcut_smoother, &mut self.highcut_smoother] {
+            smoother.reset();
+        }
+    }
+
+    // only the sidechain's magnitudes, the main input is turned down as it is
+    fn spectrum_values(&self) -> SpectrumValues {
+        SpectrumValues {
+            magnitude: true,
+            ..SpectrumValues::NONE
+        }
+    }
+
+    fn process_spectrum(
+        &mut self,
+        input: &[FFTData],
+        aux: Option<&[FFTData]>,
+        output_buffer: &mut [Vec<Complex<f32>>],
+    ) {
+        let Some(aux) = aux else {
+            return;
+        };
+        // the parameters move once per hop of the main band
+        if self.band == 0 {
+            self.sidechain_gain = self.sidechain_gain_smoother.next_hop();
+            self.lowcut = self.lowcut_smoother.next_hop();
+            self.highcut = self.highcut_smoother.next_hop();
+        }
         if self.stereo_link {
-            for (i, ((db_l, db_r), (aux_mag_l, aux_mag_r))) in db[0].iter().zip(db[1].iter()).zip(aux_mag[0].iter().zip(aux_mag[1].iter())).enumerate() {
-                if freq[0][i] < self.lowcut || freq[0][i] > self.highcut {
-                    output_buffer[0][i] = Complex::from_polar(utils::db_to_gain(*db_l), phase[0][i]);
-                    output_buffer[1][i] = Complex::from_polar(utils::db_to_gain(*db_r), phase[1][i]);
-                    continue;
+            let freq = &input[0].spectrum_freq;
+            for i in 0..input[0].spectrum_db.len() {
+                let side_gain = if freq[i] < self.lowcut || freq[i] > self.highcut {
+                    0.0
+                } else {
+                    // stereo-link meaning the sidechain input is averaged over all channels
+                    let aux_mag = aux.iter().map(|data| data.spectrum_mag[i]).sum::<f32>() / aux.len() as f32;
+                    let x = utils::gain_to_db(aux_mag * self.sidechain_gain);
+                    let side_gain = if x > -50.0 {50.0 + x } else {0.0};
+                    self.reduction[i] = side_gain;
+                    side_gain
+                };
+                for (data, output) in input.iter().zip(output_buffer.iter_mut()) {
+                    output[i] = data.fft_out[i] * utils::db_to_gain(-side_gain);
                 }
-                // stereo-link meaning the sidechain input is averaged between left and right
-                let x = utils::gain_to_db((aux_mag_l + aux_mag_r) / 2.0 * self.sidechain_gain);
-                let side_gain = if x > -50.0 {50.0 + x } else {0.0};
-                let out_db_l = db_l - side_gain;
-                let out_db_r = db_r - side_gain;
-                self.reduction[i] = side_gain;
-                output_buffer[0][i] = Complex::from_polar(utils::db_to_gain(out_db_l), phase[0][i]);
-                output_buffer[1][i] = Complex::from_polar(utils::db_to_gain(out_db_r), phase[1][i]);
             }
         } else {
-            for channel in 0..2 {
-                for (i, (db, aux_mag)) in db[channel].iter().zip(aux_mag[channel].iter()).enumerate() {
-                    if freq[channel][i] < self.lowcut || freq[channel][i] > self.highcut {
-                        output_buffer[channel][i] = Complex::from_polar(utils::db_to_gain(*db), phase[channel][i]);
+            for channel in 0..input.len() {
+                let freq = &input[channel].spectrum_freq;
+                for (i, (bin, aux_mag)) in input[channel].fft_out.iter().zip(aux[channel].spectrum_mag.iter()).enumerate() {
+                    if freq[i] < self.lowcut || freq[i] > self.highcut {
+                        output_buffer[channel][i] = *bin;
 
                         if i == 0 {
                             self.reduction[i] = 0f32;
@@ -74,8 +130,7 @@ impl Peacemaker {
                     } 
                     let x = utils::gain_to_db(aux_mag * self.sidechain_gain);
                     let side_gain = if x > -50.0 {50.0 + x } else {0.0};
-                    let out_db = db - side_gain;
-                    output_buffer[channel][i] = Complex::from_polar(utils::db_to_gain(out_db), phase[channel][i]);
+                    output_buffer[channel][i] = bin * utils::db_to_gain(-side_gain);
 
                     if i == 0 {
                         self.reduction[i] = side_gain;
@@ -88,6 +143,34 @@ impl Peacemaker {
         }
         
     }
+
+    fn analyze(&mut self, output: &[FFTData], aux: Option<&[FFTData]>, fft_size: usize) {
+        let num_bins = utils::fft_size_to_bins(fft_size);
+        let analyzer_input = self.analyzer_input_data.input_buffer();
+        analyzer_input.spectra.magnitudes.fill(0.0f32);
+        analyzer_input.reduction.fill(0.0f32);
+        analyzer_input.spectra.num_bins = num_bins;
+        for (i, mag) in analyzer_input.spectra.magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = fft_data::average_db(output, i);
+        }
+        for (i, mag) in analyzer_input.spectra.input_magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = fft_data::average_input_db(output, i);
+        }
+        if let Some(aux) = aux {
+            for (i, mag) in analyzer_input.spectra.aux_magnitudes[0..num_bins].iter_mut().enumerate() {
+                *mag = fft_data::average_input_db(aux, i);
+            }
+        }
+
+        analyzer_input.lowcut = self.lowcut;
+        analyzer_input.highcut = self.highcut;
+
+        for (i, reduction) in analyzer_input.reduction[0..num_bins].iter_mut().enumerate() {
+            *reduction = self.reduction[i];
+        }
+        self.analyzer_layers.process_spectra(&mut analyzer_input.spectra, aux.is_some());
+        self.analyzer_input_data.publish();
+    }
 }
 
 pub fn sidechain_gain_calc(x: f32) -> f32 {
@@ -1,4 +1 @@
-pub mod stereo_fft_processor;
-pub mod fft_data;
-pub mod fft_size;
-pub mod peacemaker;
\ No newline at end of file
+pub mod phase_transfer;
\ No newline at end of file
@@ -7,17 +7,5 @@ pub enum AnalyzerChannel {
     Merged
 }
 
-#[derive(Debug, Clone)]
-pub struct AnalyzerData {
-    pub num_bins: usize,
-    pub magnitudes: Vec<f32>,
-}
-
-impl AnalyzerData {
-    pub fn new(num_bins: usize, sr: usize) -> Self {
-        Self {
-            num_bins,
-            magnitudes: vec![0.0f32; num_bins],
-        }
-    }
-}
\ No newline at end of file
+// nothing but the spectra the shared editor views draw
+pub type AnalyzerData = spectral_core::analyzer::AnalyzerSpectra;
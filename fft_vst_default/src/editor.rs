@@ -1,23 +1,22 @@
 use std::sync::{Arc, Mutex};
 
-use analyzer::Analyzer;
 use nih_plug::prelude::{util, AtomicF32, Editor, Vst3Plugin};
 use nih_plug_vizia::vizia::image::{Pixel, Pixels};
 use nih_plug_vizia::vizia::{prelude::*, vg};
 use nih_plug_vizia::widgets::*;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
+use spectral_core::editor::{analyzer::Analyzer, AnalyzerParams};
 
 use crate::analyzer_data::AnalyzerData;
 use crate::PluginParams;
 
 mod param_knob;
-mod analyzer;
 
 pub const COMFORTAA_LIGHT_TTF: &[u8] = include_bytes!("../res/Comfortaa-Light.ttf");
 pub const COMFORTAA: &str = "Comfortaa";
 
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    ViziaState::new(|| (500, 350))
+    ViziaState::new(|| (500, 385))
 }
 
 #[derive(Clone, Lens)]
@@ -29,6 +28,12 @@ pub struct EditorData {
 
 impl Model for EditorData {}
 
+impl AnalyzerParams for PluginParams {
+    fn analyzer_axes(&self) -> &Mutex<[f32; 4]> {
+        &self.analyzer_axes
+    }
+}
+
 pub(crate) fn create(
     editor_state: Arc<ViziaState>,
     editor_data: EditorData,
@@ -57,13 +62,25 @@ pub(crate) fn create(
             .child_right(Stretch(1.0))
             .max_height(Pixels(50.0));
 
-            Analyzer::new(cx, EditorData::analyzer_data, EditorData::sample_rate)
+            Analyzer::new(cx, EditorData::plugin_data, EditorData::analyzer_data, EditorData::sample_rate)
             //.max_width(Pixels(450.0))
             .max_height(Pixels(200.0))
             .border_width(Pixels(2.0))
             .border_color(Color::black())
             .left(Pixels(1.0)).right(Pixels(1.0));
 
+            // ANALYZER LAYERS
+            HStack::new(cx, |cx| {
+                ParamButton::new(cx, EditorData::plugin_data, |params| &params.analyzer_show_input)
+                .with_label("Input");
+                ParamButton::new(cx, EditorData::plugin_data, |params| &params.analyzer_show_output)
+                .with_label("Output");
+            }).child_left(Stretch(1.0))
+            .child_right(Stretch(1.0))
+            .col_between(Pixels(10.0))
+            .top(Pixels(5.0))
+            .max_height(Pixels(30.0));
+
             // BOTTOM BAR FOR MISC INFO IN ALL PLUGINS (FFT SIZE AND ANALYZER CHANNEL)
             HStack::new(cx, |cx| {
                 HStack::new(cx, |cx| {
@@ -101,6 +118,23 @@ pub(crate) fn create(
                 .child_left(Stretch(1.0))
                 .child_right(Stretch(1.0))
                 .width(Pixels(230.0));
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Analyzer Mode:")
+                    .font_family(vec![FamilyOwned::Name(String::from(COMFORTAA))])
+                    .font_size(13.0)
+                    .left(Stretch(1.0))
+                    .right(Pixels(7.0))
+                    .top(Pixels(5.0));
+
+                    ParamSlider::new(cx, EditorData::plugin_data, |params| &params.analyzer_mode).font_family(vec![FamilyOwned::Name(String::from(COMFORTAA))])
+                    .font_size(13.0)
+                    .top(Pixels(5.0))
+                    .max_width(Pixels(100.0))
+                    .max_height(Pixels(20.0));
+                })
+                .child_left(Stretch(1.0))
+                .child_right(Stretch(1.0))
+                .width(Pixels(230.0));
             }).child_left(Stretch(1.0))
             .child_right(Stretch(1.0))
             .max_height(Pixels(50.0));
@@ -1,3 +0,0 @@
-pub mod stereo_fft_processor;
-pub mod fft_data;
-pub mod fft_size;
\ No newline at end of file
@@ -0,0 +1,78 @@
+use realfft::num_complex::Complex;
+use spectral_core::{analyzer::AnalyzerLayers, fft_data::{self, FFTData}, spectral_effect::{SpectralEffect, SpectrumValues}, utils};
+
+use crate::analyzer_data::{AnalyzerChannel, AnalyzerData};
+
+pub struct FFTEffect {
+    analyzer_input_data: triple_buffer::Input<AnalyzerData>,
+    analyzer_channel: AnalyzerChannel,
+    pub analyzer_layers: AnalyzerLayers,
+}
+
+impl FFTEffect {
+    pub fn new(analyzer_buffer: triple_buffer::Input<AnalyzerData>) -> Self {
+        Self {
+            analyzer_input_data: analyzer_buffer,
+            analyzer_channel: AnalyzerChannel::Merged,
+            analyzer_layers: AnalyzerLayers::new(),
+        }
+    }
+
+    pub fn set_params(&mut self, an_chan: AnalyzerChannel) {
+        self.analyzer_channel = an_chan;
+    }
+}
+
+impl SpectralEffect for FFTEffect {
+    fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.analyzer_layers.set_hop_rate(sample_rate, hop_size);
+    }
+
+    fn reset(&mut self) {
+        self.analyzer_layers.reset();
+    }
+
+    // only the complex bins, see `SpectrumValues` for what else can be asked for
+    fn spectrum_values(&self) -> SpectrumValues {
+        SpectrumValues::NONE
+    }
+
+    fn process_spectrum(
+        &mut self,
+        input: &[FFTData],
+        _aux: Option<&[FFTData]>,
+        output: &mut [Vec<Complex<f32>>],
+    ) {
+        // do your processing here
+        for (data, out) in input.iter().zip(output.iter_mut()) {
+            for (i, x) in data.fft_out.iter().enumerate() {
+                out[i] = *x;
+            }
+        }
+    }
+
+    fn analyze(&mut self, output: &[FFTData], _aux: Option<&[FFTData]>, fft_size: usize) {
+        let num_bins = utils::fft_size_to_bins(fft_size);
+        let analyzer_input = self.analyzer_input_data.input_buffer();
+        analyzer_input.magnitudes.fill(0.0f32);
+        analyzer_input.num_bins = num_bins;
+        // mono only has a left channel
+        let right = output.get(1).unwrap_or(&output[0]);
+        for (i, mag) in analyzer_input.magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].spectrum_db[i],
+                AnalyzerChannel::Right => right.spectrum_db[i],
+                AnalyzerChannel::Merged => fft_data::average_db(output, i),
+            };
+        }
+        for (i, mag) in analyzer_input.input_magnitudes[0..num_bins].iter_mut().enumerate() {
+            *mag = match self.analyzer_channel {
+                AnalyzerChannel::Left => output[0].analyzer_input_db[i],
+                AnalyzerChannel::Right => right.analyzer_input_db[i],
+                AnalyzerChannel::Merged => fft_data::average_input_db(output, i),
+            };
+        }
+        self.analyzer_layers.process_spectra(analyzer_input, false);
+        self.analyzer_input_data.publish();
+    }
+}
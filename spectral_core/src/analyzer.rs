@@ -0,0 +1,438 @@
+use nih_plug::prelude::Enum;
+
+use crate::{fft_size::FFTSize, note, utils};
+
+/// Lowest level the analyzer shows, what silent bins come out as.
+pub const FLOOR_DB: f32 = -120.0;
+pub const MAX_RMS_FRAMES: usize = 32;
+
+/// How the analyzer's main curve follows the spectrum from hop to hop.
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum AnalyzerMode {
+    Instantaneous,
+    // exponential average of the dB values
+    Average,
+    // power averaged over the last N hops
+    #[name = "RMS"]
+    Rms,
+}
+
+/// Smooths the per-hop analyzer spectrum so the display doesn't jitter, and keeps a peak-hold
+/// curve next to it. Runs on the audio thread once per hop, so the time constants don't depend on
+/// the editor's frame rate and no peak is missed.
+pub struct AnalyzerBallistics {
+    mode: AnalyzerMode,
+    average_ms: f32,
+    rms_frames: usize,
+    peak_hold: bool,
+    peak_decay_db_per_s: f32,
+    hop_rate: f32,
+
+    // bins the state below is for, zero when it has to start over from the next spectrum
+    num_bins: usize,
+    // the average in dB, or the sum of the power of the RMS frames
+    state: Vec<f32>,
+    // `rms_frames` rows of power values, as a ring buffer
+    history: Vec<f32>,
+    history_pos: usize,
+    frames_seen: usize,
+    peaks: Vec<f32>,
+}
+
+impl AnalyzerBallistics {
+    /// Allocates for the largest `FFTSize`.
+    pub fn new() -> Self {
+        let max_bins = FFTSize::MAX.num_bins();
+        Self {
+            mode: AnalyzerMode::Average,
+            average_ms: 100.0,
+            rms_frames: 8,
+            peak_hold: true,
+            peak_decay_db_per_s: 20.0,
+            hop_rate: 44100.0 / 256.0,
+
+            num_bins: 0,
+            state: Vec::with_capacity(max_bins),
+            history: vec![0.0; max_bins * MAX_RMS_FRAMES],
+            history_pos: 0,
+            frames_seen: 0,
+            peaks: Vec::with_capacity(max_bins),
+        }
+    }
+
+    pub fn set_params(&mut self, mode: AnalyzerMode, average_ms: f32, rms_frames: usize, peak_hold: bool, peak_decay_db_per_s: f32) {
+        let rms_frames = rms_frames.clamp(1, MAX_RMS_FRAMES);
+        if mode != self.mode || rms_frames != self.rms_frames {
+            self.reset();
+        }
+        self.mode = mode;
+        self.average_ms = average_ms;
+        self.rms_frames = rms_frames;
+        self.peak_hold = peak_hold;
+        self.peak_decay_db_per_s = peak_decay_db_per_s;
+    }
+
+    /// Takes the values `SpectralEffect::set_sample_rate` is called with, the analyzer runs at the
+    /// main band's hop rate.
+    pub fn set_hop_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.hop_rate = sample_rate as f32 / hop_size.max(1) as f32;
+    }
+
+    pub fn peak_hold(&self) -> bool {
+        self.peak_hold
+    }
+
+    /// Starts over from the next spectrum.
+    pub fn reset(&mut self) {
+        self.num_bins = 0;
+    }
+
+    /// Runs one hop's spectrum in dB through the ballistics. `magnitudes` is replaced by the curve
+    /// of the current mode and `peaks` gets the peak-hold curve, both need to be as long as the
+    /// spectrum.
+    pub fn process(&mut self, magnitudes: &mut [f32], peaks: &mut [f32]) {
+        let num_bins = magnitudes.len();
+        if num_bins != self.num_bins {
+            self.start(magnitudes);
+        }
+
+        let decay = self.peak_decay_db_per_s / self.hop_rate;
+        for ((peak, &db), out) in self.peaks.iter_mut().zip(magnitudes.iter()).zip(peaks.iter_mut()) {
+            *peak = db.max(*peak - decay).max(FLOOR_DB);
+            *out = *peak;
+        }
+
+        match self.mode {
+            AnalyzerMode::Instantaneous => (),
+            AnalyzerMode::Average => {
+                let coeff = (-1.0 / (self.average_ms.max(1.0) * 0.001 * self.hop_rate)).exp();
+                for (average, db) in self.state.iter_mut().zip(magnitudes.iter_mut()) {
+                    *average = *db + coeff * (*average - *db);
+                    *db = *average;
+                }
+            }
+            AnalyzerMode::Rms => self.process_rms(magnitudes),
+        }
+    }
+
+    fn process_rms(&mut self, magnitudes: &mut [f32]) {
+        let num_bins = self.num_bins;
+        let row = &mut self.history[self.history_pos * num_bins..(self.history_pos + 1) * num_bins];
+        for ((sum, old), db) in self.state.iter_mut().zip(row.iter_mut()).zip(magnitudes.iter()) {
+            let power = 10f32.powf(db * 0.1);
+            *sum += power - *old;
+            *old = power;
+        }
+
+        self.history_pos = (self.history_pos + 1) % self.rms_frames;
+        self.frames_seen = (self.frames_seen + 1).min(self.rms_frames);
+        // the running sums drift, they're added up again once per round through the frames
+        if self.history_pos == 0 {
+            self.state.fill(0.0);
+            for row in self.history[..self.rms_frames * num_bins].chunks_exact(num_bins) {
+                for (sum, power) in self.state.iter_mut().zip(row.iter()) {
+                    *sum += power;
+                }
+            }
+        }
+
+        let count = self.frames_seen as f32;
+        for (db, sum) in magnitudes.iter_mut().zip(self.state.iter()) {
+            *db = (10.0 * (sum.max(0.0) / count).log10()).max(FLOOR_DB);
+        }
+    }
+
+    fn start(&mut self, magnitudes: &[f32]) {
+        self.num_bins = magnitudes.len();
+        self.peaks.clear();
+        self.peaks.extend_from_slice(magnitudes);
+        self.state.clear();
+        match self.mode {
+            AnalyzerMode::Rms => self.state.resize(self.num_bins, 0.0),
+            _ => self.state.extend_from_slice(magnitudes),
+        }
+        self.history[..self.num_bins * self.rms_frames].fill(0.0);
+        self.history_pos = 0;
+        self.frames_seen = 0;
+    }
+}
+
+impl Default for AnalyzerBallistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One analyzer frame as the editors get it: the curves of `AnalyzerLayers` in dB and which of
+/// them are shown. Plugins whose editors draw more than that carry one of these in their own
+/// `AnalyzerData`.
+#[derive(Debug, Clone)]
+pub struct AnalyzerSpectra {
+    pub num_bins: usize,
+    pub magnitudes: Vec<f32>,
+    pub peaks: Vec<f32>,
+    pub peak_hold: bool,
+    // the unprocessed input, and the aux input of plugins that have one
+    pub input_magnitudes: Vec<f32>,
+    pub aux_magnitudes: Vec<f32>,
+    pub show_input: bool,
+    pub show_output: bool,
+    pub show_aux: bool,
+    // see `AnalyzerLayers::frame`
+    pub frame: u64,
+}
+
+impl AnalyzerSpectra {
+    pub fn new(num_bins: usize) -> Self {
+        Self {
+            num_bins,
+            magnitudes: vec![FLOOR_DB; num_bins],
+            peaks: vec![FLOOR_DB; num_bins],
+            peak_hold: false,
+            input_magnitudes: vec![FLOOR_DB; num_bins],
+            aux_magnitudes: vec![FLOOR_DB; num_bins],
+            show_input: false,
+            show_output: true,
+            show_aux: false,
+            frame: 0,
+        }
+    }
+}
+
+/// The curves the analyzer can show, each through its own `AnalyzerBallistics` with the same
+/// settings: the processed output with its peak-hold curve, the unprocessed input and the aux input.
+pub struct AnalyzerLayers {
+    pub show_input: bool,
+    pub show_output: bool,
+    pub show_aux: bool,
+
+    output: AnalyzerBallistics,
+    input: AnalyzerBallistics,
+    aux: AnalyzerBallistics,
+    // only the output gets a peak-hold curve, the other ones are written here
+    unused_peaks: Vec<f32>,
+    frame: u64,
+}
+
+impl AnalyzerLayers {
+    pub fn new() -> Self {
+        Self {
+            show_input: true,
+            show_output: true,
+            show_aux: false,
+
+            output: AnalyzerBallistics::new(),
+            input: AnalyzerBallistics::new(),
+            aux: AnalyzerBallistics::new(),
+            unused_peaks: Vec::with_capacity(FFTSize::MAX.num_bins()),
+            frame: 0,
+        }
+    }
+
+    /// See `AnalyzerBallistics::set_params`, the settings are the same for every layer.
+    pub fn set_params(&mut self, mode: AnalyzerMode, average_ms: f32, rms_frames: usize, peak_hold: bool, peak_decay_db_per_s: f32) {
+        for ballistics in [&mut self.output, &mut self.input, &mut self.aux] {
+            ballistics.set_params(mode, average_ms, rms_frames, peak_hold, peak_decay_db_per_s);
+        }
+    }
+
+    pub fn set_visible(&mut self, input: bool, output: bool, aux: bool) {
+        self.show_input = input;
+        self.show_output = output;
+        self.show_aux = aux;
+    }
+
+    pub fn set_hop_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        for ballistics in [&mut self.output, &mut self.input, &mut self.aux] {
+            ballistics.set_hop_rate(sample_rate, hop_size);
+        }
+    }
+
+    pub fn peak_hold(&self) -> bool {
+        self.output.peak_hold()
+    }
+
+    /// Counts the processed frames, so a view can tell a new frame from one it has already seen.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn reset(&mut self) {
+        for ballistics in [&mut self.output, &mut self.input, &mut self.aux] {
+            ballistics.reset();
+        }
+    }
+
+    /// Runs every shown layer through its ballistics, see `AnalyzerBallistics::process`. Hidden
+    /// layers start over once they're shown again.
+    pub fn process(&mut self, output: &mut [f32], peaks: &mut [f32], input: &mut [f32], aux: Option<&mut [f32]>) {
+        self.frame = self.frame.wrapping_add(1);
+        Self::process_layer(&mut self.output, self.show_output, output, peaks);
+        self.unused_peaks.resize(input.len(), FLOOR_DB);
+        Self::process_layer(&mut self.input, self.show_input, input, &mut self.unused_peaks);
+        match aux {
+            Some(aux) => Self::process_layer(&mut self.aux, self.show_aux, aux, &mut self.unused_peaks),
+            None => self.aux.reset(),
+        }
+    }
+
+    /// `process` for the first `num_bins` of a frame's curves, which also get the layers' flags and
+    /// the frame count. The aux layer is only shown with `has_aux`.
+    pub fn process_spectra(&mut self, spectra: &mut AnalyzerSpectra, has_aux: bool) {
+        let num_bins = spectra.num_bins;
+        self.process(
+            &mut spectra.magnitudes[..num_bins],
+            &mut spectra.peaks[..num_bins],
+            &mut spectra.input_magnitudes[..num_bins],
+            has_aux.then(|| &mut spectra.aux_magnitudes[..num_bins]),
+        );
+        spectra.peak_hold = self.peak_hold();
+        spectra.show_input = self.show_input;
+        spectra.show_output = self.show_output;
+        spectra.show_aux = self.show_aux && has_aux;
+        spectra.frame = self.frame;
+    }
+
+    fn process_layer(ballistics: &mut AnalyzerBallistics, show: bool, magnitudes: &mut [f32], peaks: &mut [f32]) {
+        if show {
+            ballistics.process(magnitudes, &mut peaks[..magnitudes.len()]);
+        } else {
+            ballistics.reset();
+        }
+    }
+}
+
+impl Default for AnalyzerLayers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The level at `freq_hz` of a spectrum in dB with bins `bin_hz` apart, interpolated between the
+/// two nearest bins.
+pub fn interpolate_db(magnitudes: &[f32], bin_hz: f32, freq_hz: f32) -> f32 {
+    if magnitudes.is_empty() {
+        return FLOOR_DB;
+    }
+    let last_bin = magnitudes.len() - 1;
+    let position = (freq_hz / bin_hz).clamp(0.0, last_bin as f32);
+    let idx = (position as usize).min(last_bin);
+    let next = (idx + 1).min(last_bin);
+    utils::lerp(magnitudes[idx], magnitudes[next], position - idx as f32)
+}
+
+/// What the editor's analyzer shows for the frequency under the mouse, e.g.
+/// `1046.5 Hz  -12.3 dB  C6 +0 ct`.
+pub fn cursor_readout(freq_hz: f32, db: f32) -> String {
+    match note::nearest_note(freq_hz) {
+        Some(note) => format!("{freq_hz:.1} Hz  {db:.1} dB  {note}"),
+        None => format!("{freq_hz:.1} Hz  {db:.1} dB"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(ballistics: &mut AnalyzerBallistics, db: f32, hops: usize) -> (f32, f32) {
+        let (mut magnitudes, mut peaks) = ([0f32; 4], [0f32; 4]);
+        for _ in 0..hops {
+            magnitudes.fill(db);
+            ballistics.process(&mut magnitudes, &mut peaks);
+        }
+        (magnitudes[1], peaks[1])
+    }
+
+    #[test]
+    fn average_and_peak_ballistics_test() {
+        let mut ballistics = AnalyzerBallistics::new();
+        // 100 hops per second
+        ballistics.set_hop_rate(44100, 441);
+        ballistics.set_params(AnalyzerMode::Average, 100.0, 8, true, 20.0);
+
+        assert_eq!(run(&mut ballistics, -20.0, 1), (-20.0, -20.0));
+        // one time constant later the average is 63% of the way there
+        let (average, peak) = run(&mut ballistics, -80.0, 10);
+        assert!((average - (-20.0 - 60.0 * 0.632)).abs() < 0.5, "average {average}");
+        // the peak falls at 20 dB/s
+        assert!((peak - -22.0).abs() < 1e-3, "peak {peak}");
+
+        ballistics.set_params(AnalyzerMode::Instantaneous, 100.0, 8, true, 20.0);
+        assert_eq!(run(&mut ballistics, -50.0, 1).0, -50.0);
+    }
+
+    #[test]
+    fn rms_averages_power_test() {
+        let mut ballistics = AnalyzerBallistics::new();
+        ballistics.set_params(AnalyzerMode::Rms, 100.0, 4, true, 20.0);
+
+        // two hops at full level and two silent ones are half the power
+        run(&mut ballistics, 0.0, 2);
+        let (rms, _) = run(&mut ballistics, FLOOR_DB, 2);
+        assert!((rms - -3.0103).abs() < 1e-3, "rms {rms}");
+
+        // only the last four hops count
+        let (rms, _) = run(&mut ballistics, -10.0, 9);
+        assert!((rms - -10.0).abs() < 1e-3, "rms {rms}");
+    }
+
+    #[test]
+    fn hidden_layers_start_over_test() {
+        let mut layers = AnalyzerLayers::new();
+        layers.set_hop_rate(44100, 441);
+        let hop = |layers: &mut AnalyzerLayers, db: f32| {
+            let (mut output, mut peaks, mut input) = ([db; 4], [0f32; 4], [db; 4]);
+            layers.process(&mut output, &mut peaks, &mut input, None);
+            input[1]
+        };
+
+        hop(&mut layers, -20.0);
+        // a shown layer is averaged
+        assert!(hop(&mut layers, -80.0) > -80.0);
+
+        layers.set_visible(false, true, false);
+        hop(&mut layers, -80.0);
+        layers.set_visible(true, true, false);
+        // the first hop after showing it again is taken as it is
+        assert_eq!(hop(&mut layers, -40.0), -40.0);
+        assert_eq!(layers.frame(), 4);
+    }
+
+    #[test]
+    fn process_spectra_test() {
+        let mut layers = AnalyzerLayers::new();
+        layers.set_hop_rate(44100, 441);
+        layers.set_params(AnalyzerMode::Instantaneous, 100.0, 1, true, 12.0);
+        layers.set_visible(true, true, true);
+        let mut spectra = AnalyzerSpectra::new(8);
+        spectra.num_bins = 4;
+        spectra.magnitudes.fill(-30.0);
+        spectra.aux_magnitudes.fill(-50.0);
+
+        // without an aux input its layer stays hidden and untouched
+        layers.process_spectra(&mut spectra, false);
+        assert_eq!((spectra.peak_hold, spectra.show_input, spectra.show_aux), (true, true, false));
+        assert_eq!(spectra.frame, 1);
+        assert_eq!(spectra.peaks[..4], [-30.0; 4]);
+        // only the bins in use
+        assert_eq!(spectra.peaks[4], FLOOR_DB);
+        assert_eq!(spectra.aux_magnitudes[0], -50.0);
+
+        layers.process_spectra(&mut spectra, true);
+        assert!(spectra.show_aux);
+        assert_eq!(spectra.frame, 2);
+    }
+
+    #[test]
+    fn cursor_readout_test() {
+        let magnitudes = [-60.0, -20.0, -40.0];
+        // halfway between the 100 Hz and the 200 Hz bin
+        assert_eq!(interpolate_db(&magnitudes, 100.0, 150.0), -30.0);
+        assert_eq!(interpolate_db(&magnitudes, 100.0, 1000.0), -40.0);
+
+        assert_eq!(cursor_readout(440.0, -30.04), "440.0 Hz  -30.0 dB  A4 +0 ct");
+        assert_eq!(cursor_readout(15_000.0, -90.0), "15000.0 Hz  -90.0 dB  A#9 +10 ct");
+        // below C0 there's no note
+        assert_eq!(cursor_readout(10.0, -90.0), "10.0 Hz  -90.0 dB");
+    }
+}
@@ -0,0 +1,213 @@
+use crate::analyzer::FLOOR_DB;
+
+/// How far the analyzer's axes can be zoomed out and panned.
+pub const MIN_HZ: f32 = 10.0;
+pub const MAX_HZ: f32 = 24_000.0;
+pub const MIN_DB: f32 = FLOOR_DB;
+pub const MAX_DB: f32 = 24.0;
+
+// and how far they can be zoomed in, one octave and 12 dB
+const MIN_LN_SPAN: f32 = std::f32::consts::LN_2;
+const MIN_DB_SPAN: f32 = 12.0;
+// steps between the level gridlines, the first one that doesn't need more than `MAX_DB_LINES`
+const DB_STEPS: [f32; 6] = [3.0, 6.0, 10.0, 20.0, 30.0, 60.0];
+const MAX_DB_LINES: f32 = 8.0;
+
+/// The frequency and level ranges the editor's analyzer shows, log frequency on the x-axis and dB
+/// on the y-axis. Positions are relative, `[0, 1]` from the left and from the bottom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalyzerAxes {
+    pub min_hz: f32,
+    pub max_hz: f32,
+    pub min_db: f32,
+    pub max_db: f32,
+}
+
+impl AnalyzerAxes {
+    pub fn new(min_hz: f32, max_hz: f32, min_db: f32, max_db: f32) -> Self {
+        let mut axes = Self { min_hz, max_hz, min_db, max_db };
+        // ranges that fit are kept as they are, rounding them through the logs would let them drift
+        if min_hz < MIN_HZ || max_hz > MAX_HZ || (max_hz / min_hz).ln() < MIN_LN_SPAN {
+            let start = min_hz.max(MIN_HZ).ln();
+            axes.set_ln_frequency_range(start, max_hz.max(MIN_HZ).ln() - start);
+        }
+        if min_db < MIN_DB || max_db > MAX_DB || max_db - min_db < MIN_DB_SPAN {
+            axes.set_db_range(min_db, max_db - min_db);
+        }
+        axes
+    }
+
+    /// For the plugins' persisted `analyzer_axes` field, the ranges are checked again since they
+    /// come from a saved state.
+    pub fn from_array([min_hz, max_hz, min_db, max_db]: [f32; 4]) -> Self {
+        if [min_hz, max_hz, min_db, max_db].iter().all(|value| value.is_finite()) {
+            Self::new(min_hz, max_hz, min_db, max_db)
+        } else {
+            Self::default()
+        }
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.min_hz, self.max_hz, self.min_db, self.max_db]
+    }
+
+    pub fn freq_to_x(&self, freq: f32) -> f32 {
+        (freq.max(f32::MIN_POSITIVE).ln() - self.min_hz.ln()) / (self.max_hz.ln() - self.min_hz.ln())
+    }
+
+    pub fn x_to_freq(&self, x: f32) -> f32 {
+        (self.min_hz.ln() + x * (self.max_hz.ln() - self.min_hz.ln())).exp()
+    }
+
+    pub fn db_to_height(&self, db: f32) -> f32 {
+        (db - self.min_db) / (self.max_db - self.min_db)
+    }
+
+    pub fn height_to_db(&self, height: f32) -> f32 {
+        self.min_db + height * (self.max_db - self.min_db)
+    }
+
+    /// Scales the visible frequency range by `factor`, below 1 zooms in. The frequency at `anchor`
+    /// stays where it is.
+    pub fn zoom_frequency(&mut self, anchor: f32, factor: f32) {
+        let (start, end) = (self.min_hz.ln(), self.max_hz.ln());
+        let center = start + anchor * (end - start);
+        let span = (end - start) * factor;
+        self.set_ln_frequency_range(center - anchor * span, span);
+    }
+
+    /// Like `zoom_frequency`, for the levels with `anchor` from the bottom.
+    pub fn zoom_db(&mut self, anchor: f32, factor: f32) {
+        let center = self.height_to_db(anchor);
+        let span = (self.max_db - self.min_db) * factor;
+        self.set_db_range(center - anchor * span, span);
+    }
+
+    /// Moves the visible ranges by a fraction of themselves, positive values move towards higher
+    /// frequencies and levels.
+    pub fn pan(&mut self, x: f32, y: f32) {
+        let (start, end) = (self.min_hz.ln(), self.max_hz.ln());
+        self.set_ln_frequency_range(start + x * (end - start), end - start);
+        let span = self.max_db - self.min_db;
+        self.set_db_range(self.min_db + y * span, span);
+    }
+
+    /// The frequency gridlines in view, every 1 to 9 times a power of ten. The 1, 2 and 5 ones are
+    /// labeled, or all of them once less than a decade is visible.
+    pub fn frequency_gridlines(&self) -> Vec<(f32, Option<String>)> {
+        let label_all = self.max_hz / self.min_hz < 10.0;
+        let mut gridlines = Vec::new();
+        for exponent in self.min_hz.log10().floor() as i32..=self.max_hz.log10().floor() as i32 {
+            let decade = 10f32.powi(exponent);
+            for multiple in 1..10 {
+                let freq = multiple as f32 * decade;
+                // with some slack, the ends can be a bit off after zooming
+                if freq < self.min_hz * 0.999 || freq > self.max_hz * 1.001 {
+                    continue;
+                }
+                let label = (label_all || matches!(multiple, 1 | 2 | 5)).then(|| frequency_label(freq));
+                gridlines.push((freq, label));
+            }
+        }
+        gridlines
+    }
+
+    /// The level gridlines in view, spaced so there aren't too many of them.
+    pub fn db_gridlines(&self) -> Vec<f32> {
+        let span = self.max_db - self.min_db;
+        let step = DB_STEPS.into_iter().find(|step| span / step <= MAX_DB_LINES).unwrap_or(DB_STEPS[DB_STEPS.len() - 1]);
+        let first = (self.min_db / step).ceil() as i32;
+        let last = (self.max_db / step).floor() as i32;
+        (first..=last).map(|idx| idx as f32 * step).collect()
+    }
+
+    // a span that doesn't fit moves the range back inside the limits instead of shrinking it
+    fn set_ln_frequency_range(&mut self, start: f32, span: f32) {
+        let (min, max) = (MIN_HZ.ln(), MAX_HZ.ln());
+        let span = span.clamp(MIN_LN_SPAN, max - min);
+        let start = start.clamp(min, max - span);
+        self.min_hz = start.exp();
+        self.max_hz = (start + span).exp();
+    }
+
+    fn set_db_range(&mut self, start: f32, span: f32) {
+        let span = span.clamp(MIN_DB_SPAN, MAX_DB - MIN_DB);
+        self.min_db = start.clamp(MIN_DB, MAX_DB - span);
+        self.max_db = self.min_db + span;
+    }
+}
+
+impl Default for AnalyzerAxes {
+    fn default() -> Self {
+        Self::new(20.0, 20_000.0, -80.0, 20.0)
+    }
+}
+
+/// `20`, `500`, `1k`, `2.5k`.
+pub fn frequency_label(freq: f32) -> String {
+    let freq = (freq * 10.0).round() / 10.0;
+    if freq >= 1000.0 {
+        format!("{}k", (freq / 100.0).round() / 10.0)
+    } else {
+        format!("{freq}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-2 * b.abs().max(1.0), "{a} != {b}");
+    }
+
+    #[test]
+    fn zoom_and_pan_test() {
+        let mut axes = AnalyzerAxes::default();
+        assert_close(axes.x_to_freq(axes.freq_to_x(1000.0)), 1000.0);
+        assert_close(axes.height_to_db(axes.db_to_height(-30.0)), -30.0);
+
+        // the frequency under the mouse stays there
+        let x = axes.freq_to_x(1000.0);
+        axes.zoom_frequency(x, 0.5);
+        assert_close(axes.freq_to_x(1000.0), x);
+        assert_close(axes.max_hz / axes.min_hz, 1000f32.sqrt());
+
+        axes.zoom_db(0.5, 0.5);
+        assert_close(axes.min_db, -55.0);
+        assert_close(axes.max_db, -5.0);
+
+        // panning stops at the limits without changing the zoom
+        axes.pan(-100.0, 100.0);
+        assert_close(axes.min_hz, MIN_HZ);
+        assert_close(axes.max_hz, MIN_HZ * 1000f32.sqrt());
+        assert_close(axes.max_db, MAX_DB);
+        assert_close(axes.max_db - axes.min_db, 50.0);
+
+        // and so does zooming
+        axes.zoom_frequency(0.5, 1e-3);
+        assert_close(axes.max_hz / axes.min_hz, 2.0);
+        axes.zoom_db(0.5, 100.0);
+        assert_eq!((axes.min_db, axes.max_db), (MIN_DB, MAX_DB));
+
+        assert_eq!(AnalyzerAxes::from_array(AnalyzerAxes::default().to_array()), AnalyzerAxes::default());
+        assert_eq!(AnalyzerAxes::from_array([f32::NAN, 1.0, 2.0, 3.0]), AnalyzerAxes::default());
+    }
+
+    #[test]
+    fn gridlines_test() {
+        let axes = AnalyzerAxes::default();
+        let labels: Vec<String> = axes.frequency_gridlines().into_iter().filter_map(|(_, label)| label).collect();
+        assert_eq!(labels, ["20", "50", "100", "200", "500", "1k", "2k", "5k", "10k", "20k"]);
+        assert_eq!(axes.frequency_gridlines().len(), 28);
+        assert_eq!(axes.db_gridlines(), [-80.0, -60.0, -40.0, -20.0, 0.0, 20.0]);
+
+        // less than a decade labels every line
+        let axes = AnalyzerAxes::new(300.0, 2_000.0, -30.0, -6.0);
+        let labels: Vec<String> = axes.frequency_gridlines().into_iter().filter_map(|(_, label)| label).collect();
+        assert_eq!(labels, ["300", "400", "500", "600", "700", "800", "900", "1k", "2k"]);
+        assert_eq!(axes.db_gridlines(), [-30.0, -27.0, -24.0, -21.0, -18.0, -15.0, -12.0, -9.0, -6.0]);
+
+        assert_eq!(frequency_label(2500.0), "2.5k");
+    }
+}
@@ -0,0 +1,82 @@
+use nih_plug::prelude::Enum;
+
+/// How a stereo signal is split into the two channels the effect sees. Only used when the processor
+/// has exactly two channels, anything else is always processed channel by channel.
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum ChannelMode {
+    #[name = "Stereo L/R"]
+    Stereo,
+    #[name = "Mid/Side"]
+    MidSide,
+    // the side channel is still transformed, but its spectrum skips the effect
+    #[name = "Mid Only"]
+    Mid,
+    #[name = "Side Only"]
+    Side,
+}
+
+impl ChannelMode {
+    /// Whether the channels are mid and side instead of left and right.
+    pub fn is_mid_side(self) -> bool {
+        self != ChannelMode::Stereo
+    }
+
+    /// The channel whose unprocessed spectrum is resynthesized instead of the effect's output.
+    pub fn bypassed_channel(self) -> Option<usize> {
+        match self {
+            ChannelMode::Stereo | ChannelMode::MidSide => None,
+            ChannelMode::Mid => Some(1),
+            ChannelMode::Side => Some(0),
+        }
+    }
+}
+
+/// Turns a left and right sample into mid and side, in place.
+#[inline]
+pub fn encode_mid_side(left: &mut f32, right: &mut f32) {
+    let mid = (*left + *right) * 0.5;
+    let side = (*left - *right) * 0.5;
+    *left = mid;
+    *right = side;
+}
+
+/// Inverse of `encode_mid_side`.
+#[inline]
+pub fn decode_mid_side(mid: &mut f32, side: &mut f32) {
+    let left = *mid + *side;
+    let right = *mid - *side;
+    *mid = left;
+    *side = right;
+}
+
+/// `encode_mid_side` for every sample from `start` on, if there are exactly two `channels`.
+pub fn encode_mid_side_block(channels: &mut [&mut [f32]], start: usize) {
+    if let [left, right] = channels {
+        for (left, right) in left[start..].iter_mut().zip(right[start..].iter_mut()) {
+            encode_mid_side(left, right);
+        }
+    }
+}
+
+/// `decode_mid_side` for every sample from `start` on, if there are exactly two `channels`.
+pub fn decode_mid_side_block(channels: &mut [&mut [f32]], start: usize) {
+    if let [mid, side] = channels {
+        for (mid, side) in mid[start..].iter_mut().zip(side[start..].iter_mut()) {
+            decode_mid_side(mid, side);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mid_side_round_trip_test() {
+        let (mut left, mut right) = (0.75f32, -0.25f32);
+        encode_mid_side(&mut left, &mut right);
+        assert_eq!((left, right), (0.25, 0.5));
+        decode_mid_side(&mut left, &mut right);
+        assert_eq!((left, right), (0.75, -0.25));
+    }
+}
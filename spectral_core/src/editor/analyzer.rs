@@ -0,0 +1,445 @@
+use std::sync::{atomic::Ordering, Arc, Mutex};
+use std::time::Duration;
+
+use nih_plug::prelude::AtomicF32;
+use vizia::{prelude::*, vg};
+
+use super::{AnalyzerParams, AnalyzerPlot, AnalyzerSource};
+use crate::{analyzer, analyzer_axes::AnalyzerAxes};
+
+// one step of the mouse wheel zooms in or out by this much
+const ZOOM_STEP: f32 = 0.85;
+// how far the mouse has to move with the button down before a click becomes a drag
+const DRAG_THRESHOLD: f32 = 3.0;
+// the readout is recomputed this often, about once per frame at 60 fps
+const READOUT_INTERVAL: Duration = Duration::from_millis(16);
+
+// sent by the readout timer
+enum AnalyzerEvent {
+    UpdateReadout,
+}
+
+/// The spectrum analyzer with its gridlines, a zoomable and pannable view and a readout of the
+/// frequency under the mouse or a pinned marker. Plugins add their own curves through
+/// `AnalyzerPlot`.
+#[derive(Clone, Lens)]
+pub struct Analyzer {
+    analyzer_data: Arc<Mutex<dyn AnalyzerSource>>,
+    sample_rate: Arc<AtomicF32>,
+    params: Arc<dyn AnalyzerParams>,
+    axes: AnalyzerAxes,
+    // see `AnalyzerPlot::spectrum_height`, it doesn't change for a plugin
+    spectrum_height: f32,
+    // the mouse position while it's over the analyzer
+    cursor: Option<(f32, f32)>,
+    // where a drag started and the axes at that point
+    drag: Option<((f32, f32), AnalyzerAxes)>,
+    dragged: bool,
+    // a frequency pinned with a click
+    marker: Option<f32>,
+    readout: String,
+}
+
+impl Analyzer {
+    pub fn new<P, D, LParams, LAnalyzerData, LRate>(
+        cx: &mut Context,
+        params: LParams,
+        analyzer_data: LAnalyzerData,
+        sample_rate: LRate,
+    ) -> Handle<Self>
+    where
+        P: AnalyzerParams,
+        D: AnalyzerPlot,
+        LParams: Lens<Target = Arc<P>>,
+        LAnalyzerData: Lens<Target = Arc<Mutex<triple_buffer::Output<D>>>>,
+        LRate: Lens<Target = Arc<AtomicF32>>,
+    {
+        let params = params.get(cx);
+        let axes = AnalyzerAxes::from_array(*params.analyzer_axes().lock().unwrap());
+        let analyzer_data = analyzer_data.get(cx);
+        let spectrum_height = analyzer_data.lock().unwrap().read().spectrum_height();
+        Self {
+            analyzer_data,
+            sample_rate: sample_rate.get(cx),
+            params,
+            axes,
+            spectrum_height,
+            cursor: None,
+            drag: None,
+            dragged: false,
+            marker: None,
+            readout: String::new(),
+        }
+        .build(cx, |cx| {
+            // the level under a still mouse or marker follows the audio, not only the mouse
+            let timer = cx.add_timer(READOUT_INTERVAL, None, |cx, action| {
+                if let TimerAction::Tick(_) = action {
+                    cx.emit(AnalyzerEvent::UpdateReadout);
+                }
+            });
+            cx.start_timer(timer);
+
+            // the gridlines are drawn in `draw_gridlines`, these are their labels
+            Binding::new(cx, Analyzer::axes.map(|axes| axes.to_array().to_vec()), move |cx, axes| {
+                let axes = axes.get(cx);
+                let axes = AnalyzerAxes::new(axes[0], axes[1], axes[2], axes[3]);
+                for (freq, label) in axes.frequency_gridlines() {
+                    if let Some(label) = label {
+                        Label::new(cx, &label)
+                            .position_type(PositionType::SelfDirected)
+                            .left(Percentage(axes.freq_to_x(freq) * 100.0))
+                            .top(Stretch(1.0))
+                            .bottom(Pixels(2.0))
+                            .font_size(10.0)
+                            .hoverable(false);
+                    }
+                }
+                for db in axes.db_gridlines() {
+                    Label::new(cx, &db.to_string())
+                        .position_type(PositionType::SelfDirected)
+                        .left(Pixels(3.0))
+                        .top(Percentage(level_y(&axes, spectrum_height, db) * 100.0))
+                        .font_size(10.0)
+                        .hoverable(false);
+                }
+            });
+
+            Label::new(cx, Analyzer::readout)
+                .left(Stretch(1.0))
+                .right(Pixels(6.0))
+                .top(Pixels(4.0))
+                .hoverable(false);
+        })
+    }
+
+    // the axes are saved with the plugin's state
+    fn set_axes(&mut self, axes: AnalyzerAxes) {
+        self.axes = axes;
+        *self.params.analyzer_axes().lock().unwrap() = axes.to_array();
+    }
+
+    // the readout is for the mouse, or for the marker once the mouse leaves
+    fn update_readout(&mut self, cx: &mut EventContext) {
+        let bounds = cx.cache.get_bounds(cx.current());
+        let nyquist = self.sample_rate.load(Ordering::Relaxed) / 2.0;
+        let mut analyzer_data = self.analyzer_data.lock().unwrap();
+        let spectra = analyzer_data.read_plot().spectra();
+        if spectra.num_bins < 2 {
+            return;
+        }
+
+        let freq = match (self.cursor, self.marker) {
+            (Some((x, _)), _) => self.axes.x_to_freq((x - bounds.x) / bounds.w),
+            (None, Some(freq)) => freq,
+            (None, None) => {
+                self.readout.clear();
+                return;
+            }
+        };
+        let bin_hz = nyquist / (spectra.num_bins - 1) as f32;
+        let db = analyzer::interpolate_db(&spectra.magnitudes[..spectra.num_bins], bin_hz, freq);
+        self.readout = analyzer::cursor_readout(freq, db);
+    }
+
+    // a click pins a marker at the mouse, clicking on the marker again removes it
+    fn toggle_marker(&mut self, cx: &mut EventContext) {
+        let Some((x, _)) = self.cursor else {
+            return;
+        };
+        let bounds = cx.cache.get_bounds(cx.current());
+        self.marker = match self.marker {
+            Some(freq) if (frequency_x(bounds, &self.axes, freq) - x).abs() < 4.0 => None,
+            _ => Some(self.axes.x_to_freq((x - bounds.x) / bounds.w)),
+        };
+    }
+}
+
+impl View for Analyzer {
+    fn element(&self) -> Option<&'static str> {
+        Some("Analyzer")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|analyzer_event, _| match analyzer_event {
+            AnalyzerEvent::UpdateReadout => self.update_readout(cx),
+        });
+        event.map(|window_event, meta| match *window_event {
+            WindowEvent::MouseMove(x, y) => {
+                self.cursor = Some((x, y));
+                // dragging pans both axes
+                if let Some(((start_x, start_y), axes)) = self.drag {
+                    self.dragged |= (x - start_x).abs().max((y - start_y).abs()) > DRAG_THRESHOLD;
+                    if self.dragged {
+                        let bounds = cx.cache.get_bounds(cx.current());
+                        let mut axes = axes;
+                        axes.pan(-(x - start_x) / bounds.w, (y - start_y) / (bounds.h * self.spectrum_height));
+                        self.set_axes(axes);
+                    }
+                }
+                self.update_readout(cx);
+                cx.needs_redraw();
+            }
+            WindowEvent::MouseLeave => {
+                self.cursor = None;
+                self.update_readout(cx);
+                cx.needs_redraw();
+            }
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                if let Some(cursor) = self.cursor {
+                    self.drag = Some((cursor, self.axes));
+                    self.dragged = false;
+                    cx.capture();
+                    cx.set_active(true);
+                }
+                meta.consume();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.drag.take().is_some() {
+                    cx.release();
+                    cx.set_active(false);
+                    if !self.dragged {
+                        self.toggle_marker(cx);
+                    }
+                    self.update_readout(cx);
+                    cx.needs_redraw();
+                    meta.consume();
+                }
+            }
+            // right clicks reset the zoom, like they reset parameters
+            WindowEvent::MouseDown(MouseButton::Right) => {
+                self.set_axes(AnalyzerAxes::default());
+                self.update_readout(cx);
+                cx.needs_redraw();
+                meta.consume();
+            }
+            // the wheel zooms into the frequencies around the mouse, or into the levels with shift
+            WindowEvent::MouseScroll(_, scroll_y) => {
+                if let Some((x, y)) = self.cursor {
+                    let bounds = cx.cache.get_bounds(cx.current());
+                    let factor = ZOOM_STEP.powf(scroll_y);
+                    let mut axes = self.axes;
+                    if cx.modifiers().shift() {
+                        let level = 1.0 - (y - bounds.y) / (bounds.h * self.spectrum_height);
+                        axes.zoom_db(level.clamp(0.0, 1.0), factor);
+                    } else {
+                        axes.zoom_frequency((x - bounds.x) / bounds.w, factor);
+                    }
+                    self.set_axes(axes);
+                    self.update_readout(cx);
+                    cx.needs_redraw();
+                }
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let mut analyzer_data = self.analyzer_data.lock().unwrap();
+        let plot = analyzer_data.read_plot();
+        let nyquist = self.sample_rate.load(Ordering::Relaxed) / 2.0;
+
+        // zoomed in, the curves go past the edges
+        canvas.save();
+        canvas.intersect_scissor(bounds.x, bounds.y, bounds.w, bounds.h);
+        plot.draw_under(cx, canvas, &self.axes, nyquist);
+        draw_gridlines(cx, canvas, &self.axes, self.spectrum_height);
+        if plot.spectra().num_bins > 1 {
+            draw_spectrum(cx, canvas, plot, &self.axes, self.spectrum_height, nyquist);
+        }
+        plot.draw_over(cx, canvas, &self.axes, nyquist);
+        draw_cursor(cx, canvas, plot, &self.axes, self.spectrum_height, nyquist, self.cursor, self.marker);
+        canvas.restore();
+
+        // draw border
+        let border_width = cx.border_width();
+        let border_color: vg::Color = cx.border_color().into();
+
+        let mut path = vg::Path::new();
+        {
+            let x = bounds.x + border_width / 2.0;
+            let y = bounds.y + border_width / 2.0;
+            let w = bounds.w - border_width;
+            let h = bounds.h - border_width;
+            path.move_to(x, y);
+            path.line_to(x, y + h);
+            path.line_to(x + w, y + h);
+            path.line_to(x + w, y);
+            path.close();
+        }
+
+        let paint = vg::Paint::color(border_color).with_line_width(border_width);
+        canvas.stroke_path(&path, &paint);
+    }
+}
+
+// `[0, 1]` from the top, levels out of view stay at the edges of the spectrum's part of the view
+#[inline]
+fn level_y(axes: &AnalyzerAxes, spectrum_height: f32, db: f32) -> f32 {
+    spectrum_height * (1.0 - axes.db_to_height(db).clamp(0.0, 1.0))
+}
+
+/// Where `freq` is in the analyzer's `bounds`, for the curves plugins draw themselves.
+#[inline]
+pub fn frequency_x(bounds: BoundingBox, axes: &AnalyzerAxes, freq: f32) -> f32 {
+    bounds.x + (bounds.w * axes.freq_to_x(freq))
+}
+
+// the lines at the 1, 2 and 5 multiples of the frequency decades and at the labeled levels are
+// stronger than the ones in between
+fn draw_gridlines(cx: &mut DrawContext, canvas: &mut Canvas, axes: &AnalyzerAxes, spectrum_height: f32) {
+    let bounds = cx.bounds();
+
+    let mut minor_path = vg::Path::new();
+    let mut major_path = vg::Path::new();
+    for (freq, label) in axes.frequency_gridlines() {
+        let x = frequency_x(bounds, axes, freq);
+        let path = if label.is_some() { &mut major_path } else { &mut minor_path };
+        path.move_to(x, bounds.y);
+        path.line_to(x, bounds.y + bounds.h);
+    }
+    for db in axes.db_gridlines() {
+        let y = bounds.y + (bounds.h * level_y(axes, spectrum_height, db));
+        major_path.move_to(bounds.x, y);
+        major_path.line_to(bounds.x + bounds.w, y);
+    }
+
+    let minor_paint = vg::Paint::color(vg::Color::rgba(128, 128, 128, 40)).with_line_width(1.0);
+    canvas.stroke_path(&minor_path, &minor_paint);
+    let major_paint = vg::Paint::color(vg::Color::rgba(128, 128, 128, 100)).with_line_width(1.0);
+    canvas.stroke_path(&major_path, &major_paint);
+}
+
+fn draw_spectrum(
+    cx: &mut DrawContext,
+    canvas: &mut Canvas,
+    plot: &dyn AnalyzerPlot,
+    axes: &AnalyzerAxes,
+    spectrum_height: f32,
+    nyquist_hz: f32,
+) {
+    let bounds = cx.bounds();
+    let spectra = plot.spectra();
+    let colors = plot.spectrum_colors();
+    let bin_hz = nyquist_hz / (spectra.num_bins - 1) as f32;
+    let magnitude_y = |magnitude: f32| bounds.y + (bounds.h * level_y(axes, spectrum_height, magnitude));
+    let bottom = bounds.y + bounds.h * spectrum_height;
+
+    let mut bars_path = vg::Path::new();
+    let mut peak_path = vg::Path::new();
+    let mut input_path = vg::Path::new();
+    let mut aux_path = vg::Path::new();
+    let mut last_x = bounds.x;
+
+    for (bin_idx, (magnitude, peak)) in spectra
+        .magnitudes
+        .iter()
+        .zip(spectra.peaks.iter())
+        .enumerate()
+        .take(spectra.num_bins - 1)
+        .skip(1)
+    {
+        let physical_x_coord = frequency_x(bounds, axes, bin_idx as f32 * bin_hz);
+
+        if bin_idx == 1 {
+            bars_path.move_to(physical_x_coord, bottom);
+        }
+        bars_path.line_to(physical_x_coord, magnitude_y(*magnitude));
+
+        for (path, db) in [
+            (&mut peak_path, *peak),
+            (&mut input_path, spectra.input_magnitudes[bin_idx]),
+            (&mut aux_path, spectra.aux_magnitudes[bin_idx]),
+        ] {
+            let y = magnitude_y(db);
+            if bin_idx == 1 {
+                path.move_to(physical_x_coord, y);
+            } else {
+                path.line_to(physical_x_coord, y);
+            }
+        }
+        last_x = physical_x_coord;
+    }
+
+    // the average (or whatever the analyzer mode is) is filled, the peaks are a line on top
+    bars_path.line_to(last_x, bottom);
+    bars_path.close();
+
+    // the input goes behind the output, what the effect took away is the gap between the two
+    if spectra.show_input {
+        let input_paint = vg::Paint::color(colors.input).with_line_width(1.0);
+        canvas.stroke_path(&input_path, &input_paint);
+    }
+
+    if spectra.show_output {
+        canvas.fill_path(&bars_path, &vg::Paint::color(colors.output_fill));
+        if let Some(line) = colors.output_line {
+            canvas.stroke_path(&bars_path, &vg::Paint::color(line).with_line_width(1.0));
+        }
+
+        if spectra.peak_hold {
+            let peak_paint = vg::Paint::color(colors.peaks).with_line_width(1.0);
+            canvas.stroke_path(&peak_path, &peak_paint);
+        }
+    }
+
+    if spectra.show_aux {
+        let aux_paint = vg::Paint::color(colors.aux).with_line_width(1.0);
+        canvas.stroke_path(&aux_path, &aux_paint);
+    }
+}
+
+// a crosshair at the mouse and a line at the pinned marker, each with a dot where it crosses the
+// output spectrum
+#[allow(clippy::too_many_arguments)]
+fn draw_cursor(
+    cx: &mut DrawContext,
+    canvas: &mut Canvas,
+    plot: &dyn AnalyzerPlot,
+    axes: &AnalyzerAxes,
+    spectrum_height: f32,
+    nyquist_hz: f32,
+    cursor: Option<(f32, f32)>,
+    marker: Option<f32>,
+) {
+    let spectra = plot.spectra();
+    if spectra.num_bins < 2 {
+        return;
+    }
+    let bounds = cx.bounds();
+    let bin_hz = nyquist_hz / (spectra.num_bins - 1) as f32;
+    let curve_y = |freq: f32| {
+        let db = analyzer::interpolate_db(&spectra.magnitudes[..spectra.num_bins], bin_hz, freq);
+        bounds.y + (bounds.h * level_y(axes, spectrum_height, db))
+    };
+
+    if let Some(freq) = marker {
+        let x = frequency_x(bounds, axes, freq);
+        let mut path = vg::Path::new();
+        path.move_to(x, bounds.y);
+        path.line_to(x, bounds.y + bounds.h);
+        let paint = vg::Paint::color(vg::Color::rgb(200, 60, 160));
+        canvas.stroke_path(&path, &paint.clone().with_line_width(1.0));
+        let mut dot = vg::Path::new();
+        dot.circle(x, curve_y(freq), 3.0);
+        canvas.fill_path(&dot, &paint);
+    }
+
+    if let Some((x, y)) = cursor {
+        let mut path = vg::Path::new();
+        path.move_to(x, bounds.y);
+        path.line_to(x, bounds.y + bounds.h);
+        path.move_to(bounds.x, y);
+        path.line_to(bounds.x + bounds.w, y);
+        let paint = vg::Paint::color(vg::Color::rgba(25, 25, 25, 120));
+        canvas.stroke_path(&path, &paint.clone().with_line_width(1.0));
+        let mut dot = vg::Path::new();
+        dot.circle(x, curve_y(axes.x_to_freq((x - bounds.x) / bounds.w)), 3.0);
+        canvas.fill_path(&dot, &paint);
+    }
+}
@@ -0,0 +1,100 @@
+//! The analyzer and spectrogram views the plugins' editors share. Only built with the `editor`
+//! feature, the audio side of the crate doesn't need vizia.
+
+use std::sync::Mutex;
+
+use vizia::{prelude::*, vg};
+
+use crate::{analyzer::AnalyzerSpectra, analyzer_axes::AnalyzerAxes, spectrogram::Colormap};
+
+pub mod analyzer;
+pub mod spectrogram;
+
+/// A plugin's `AnalyzerData` as the shared views see it. Plugins that only publish the spectra
+/// use `AnalyzerSpectra` as it is, the other ones draw their own curves through the hooks.
+pub trait AnalyzerPlot: Send + 'static {
+    fn spectra(&self) -> &AnalyzerSpectra;
+
+    /// The part of the analyzer's height, from the top, that the spectrum and its dB grid take.
+    /// The rest is left to the plugin's own curves.
+    fn spectrum_height(&self) -> f32 {
+        1.0
+    }
+
+    fn spectrum_colors(&self) -> SpectrumColors {
+        SpectrumColors::default()
+    }
+
+    /// Drawn under the spectrum and the gridlines.
+    fn draw_under(&self, _cx: &mut DrawContext, _canvas: &mut Canvas, _axes: &AnalyzerAxes, _nyquist_hz: f32) {}
+
+    /// Drawn over the spectrum, under the cursor.
+    fn draw_over(&self, _cx: &mut DrawContext, _canvas: &mut Canvas, _axes: &AnalyzerAxes, _nyquist_hz: f32) {}
+}
+
+impl AnalyzerPlot for AnalyzerSpectra {
+    fn spectra(&self) -> &AnalyzerSpectra {
+        self
+    }
+}
+
+/// The editor's end of a plugin's analyzer triple buffer, so the views don't depend on the type of
+/// the plugin's `AnalyzerData`.
+pub trait AnalyzerSource: Send {
+    fn read_plot(&mut self) -> &dyn AnalyzerPlot;
+}
+
+impl<D: AnalyzerPlot> AnalyzerSource for triple_buffer::Output<D> {
+    fn read_plot(&mut self) -> &dyn AnalyzerPlot {
+        self.read()
+    }
+}
+
+/// The parameters the analyzer view reads, implemented by each plugin's `Params`.
+pub trait AnalyzerParams: Send + Sync + 'static {
+    /// The zoomed in axes as `AnalyzerAxes::to_array`, a `#[persist]` field so they're saved with
+    /// the plugin's state.
+    fn analyzer_axes(&self) -> &Mutex<[f32; 4]>;
+}
+
+/// The parameters the spectrogram view reads, implemented by the `Params` of plugins that show one.
+pub trait SpectrogramParams: Send + Sync + 'static {
+    /// The colormap and the dB range it spans, bottom first.
+    fn spectrogram_colors(&self) -> (Colormap, f32, f32);
+}
+
+/// The colors of the analyzer's curves. The default is dark curves for a light background.
+#[derive(Debug, Clone, Copy)]
+pub struct SpectrumColors {
+    pub output_fill: vg::Color,
+    // `None` leaves the filled output without an outline
+    pub output_line: Option<vg::Color>,
+    pub peaks: vg::Color,
+    pub input: vg::Color,
+    pub aux: vg::Color,
+}
+
+impl SpectrumColors {
+    /// Light curves for a dark background.
+    pub fn light() -> Self {
+        Self {
+            output_fill: vg::Color::rgb(199, 207, 221),
+            output_line: None,
+            peaks: vg::Color::rgb(230, 120, 60),
+            input: vg::Color::rgb(120, 150, 220),
+            aux: vg::Color::rgb(90, 190, 120),
+        }
+    }
+}
+
+impl Default for SpectrumColors {
+    fn default() -> Self {
+        Self {
+            output_fill: vg::Color::rgba(25, 25, 25, 90),
+            output_line: Some(vg::Color::rgb(25, 25, 25)),
+            peaks: vg::Color::rgb(220, 90, 40),
+            input: vg::Color::rgb(70, 120, 200),
+            aux: vg::Color::rgb(60, 160, 90),
+        }
+    }
+}
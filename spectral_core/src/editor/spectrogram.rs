@@ -0,0 +1,137 @@
+use std::sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex};
+
+use nih_plug::prelude::AtomicF32;
+use vizia::{image::{DynamicImage, RgbaImage}, prelude::*, vg};
+
+use super::{AnalyzerPlot, AnalyzerSource, SpectrogramParams};
+use crate::spectrogram::{SpectrogramBuffer, MIN_FREQ_HZ};
+
+// one column per new analyzer frame, at most one per redraw
+const NUM_COLUMNS: usize = 256;
+// a row per pixel of the view's height, this is only the height until the first draw
+const NUM_ROWS: usize = 128;
+
+struct SpectrogramImage {
+    pixels: DynamicImage,
+    // created on the first draw, there's no canvas before that
+    id: Option<vg::ImageId>,
+}
+
+impl SpectrogramImage {
+    fn new(num_rows: usize) -> Self {
+        Self {
+            pixels: DynamicImage::ImageRgba8(RgbaImage::new(NUM_COLUMNS as u32, num_rows as u32)),
+            id: None,
+        }
+    }
+}
+
+/// A scrolling, log frequency spectrogram of the analyzer data, newest frame on the right.
+pub struct Spectrogram {
+    analyzer_data: Arc<Mutex<dyn AnalyzerSource>>,
+    sample_rate: Arc<AtomicF32>,
+    params: Arc<dyn SpectrogramParams>,
+    buffer: Mutex<SpectrogramBuffer>,
+    image: Mutex<SpectrogramImage>,
+    // the analyzer frame that was pushed last, the view is redrawn more often than frames come in
+    last_frame: AtomicU64,
+}
+
+impl Spectrogram {
+    pub fn new<P, D, LParams, LAnalyzerData, LRate>(
+        cx: &mut Context,
+        params: LParams,
+        analyzer_data: LAnalyzerData,
+        sample_rate: LRate,
+    ) -> Handle<Self>
+    where
+        P: SpectrogramParams,
+        D: AnalyzerPlot,
+        LParams: Lens<Target = Arc<P>>,
+        LAnalyzerData: Lens<Target = Arc<Mutex<triple_buffer::Output<D>>>>,
+        LRate: Lens<Target = Arc<AtomicF32>>,
+    {
+        Self {
+            analyzer_data: analyzer_data.get(cx),
+            sample_rate: sample_rate.get(cx),
+            params: params.get(cx),
+            buffer: Mutex::new(SpectrogramBuffer::new(NUM_COLUMNS, NUM_ROWS)),
+            image: Mutex::new(SpectrogramImage::new(NUM_ROWS)),
+            last_frame: AtomicU64::new(0),
+        }
+        .build(cx, |_cx| ())
+    }
+}
+
+impl View for Spectrogram {
+    fn element(&self) -> Option<&'static str> {
+        Some("Spectrogram")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let mut buffer = self.buffer.lock().unwrap();
+        let mut image = self.image.lock().unwrap();
+        // a resized view starts over with a new image, the old one is freed right away
+        let num_rows = (bounds.h.round() as usize).max(1);
+        if buffer.num_rows() != num_rows {
+            *buffer = SpectrogramBuffer::new(NUM_COLUMNS, num_rows);
+            if let Some(id) = image.id.take() {
+                canvas.delete_image(id);
+            }
+            *image = SpectrogramImage::new(num_rows);
+        }
+
+        {
+            let mut analyzer_data = self.analyzer_data.lock().unwrap();
+            let spectra = analyzer_data.read_plot().spectra();
+            let nyquist = self.sample_rate.load(Ordering::Relaxed) / 2.0;
+            buffer.set_frequency_range(MIN_FREQ_HZ, nyquist);
+            let new_frame = self.last_frame.swap(spectra.frame, Ordering::Relaxed) != spectra.frame;
+            if new_frame && spectra.num_bins > 1 {
+                let bin_hz = nyquist / (spectra.num_bins - 1) as f32;
+                buffer.push(&spectra.magnitudes[..spectra.num_bins], bin_hz);
+            }
+        }
+
+        let (colormap, min_db, max_db) = self.params.spectrogram_colors();
+        buffer.render(colormap, min_db, max_db, image.pixels.as_mut_rgba8().unwrap());
+        let Ok(source) = vg::ImageSource::try_from(&image.pixels) else {
+            return;
+        };
+        let id = match image.id {
+            Some(id) => {
+                let _ = canvas.update_image(id, source, 0, 0);
+                id
+            }
+            None => match canvas.create_image(source, vg::ImageFlags::empty()) {
+                Ok(id) => id,
+                Err(_) => return,
+            },
+        };
+        image.id = Some(id);
+
+        let mut path = vg::Path::new();
+        path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        let paint = vg::Paint::image(id, bounds.x, bounds.y, bounds.w, bounds.h, 0.0, 1.0);
+        canvas.fill_path(&path, &paint);
+
+        // draw border
+        let border_width = cx.border_width();
+        let border_color: vg::Color = cx.border_color().into();
+        let mut path = vg::Path::new();
+        {
+            let x = bounds.x + border_width / 2.0;
+            let y = bounds.y + border_width / 2.0;
+            let w = bounds.w - border_width;
+            let h = bounds.h - border_width;
+            path.rect(x, y, w, h);
+        }
+        let paint = vg::Paint::color(border_color).with_line_width(border_width);
+        canvas.stroke_path(&path, &paint);
+    }
+}
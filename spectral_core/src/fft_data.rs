@@ -0,0 +1,110 @@
+use std::f32::consts::TAU;
+
+use realfft::num_complex::Complex;
+
+use crate::{
+    fft_size::FFTSize,
+    utils::{self, fft_size_to_bins},
+};
+
+pub struct FFTData {
+    pub fft_in: Vec<f32>,
+    pub fft_out: Vec<Complex<f32>>,
+    pub ifft_out: Vec<f32>,
+
+    pub spectrum_mag: Vec<f32>,
+    pub spectrum_phase: Vec<f32>,
+    pub spectrum_freq: Vec<f32>,
+    pub spectrum_db: Vec<f32>,
+    // `fft_out` in dB before processing, filled for the analyzer next to the processed `spectrum_db`
+    pub analyzer_input_db: Vec<f32>,
+
+    // phase of the previous frame, the phase advance is measured against it
+    pub prev_phase: Vec<f32>,
+    // unwrapped phase advance since the previous frame, in radians per hop
+    pub spectrum_phase_advance: Vec<f32>,
+    // instantaneous frequency in Hz, unlike `spectrum_freq` this isn't just the bin center
+    pub spectrum_inst_freq: Vec<f32>,
+}
+
+impl FFTData {
+    /// All buffers have room for the largest `FFTSize`, so `fft_size_change` never allocates.
+    pub fn new(fft_size: usize) -> Self {
+        let num_bins = fft_size_to_bins(fft_size);
+        let max_size = FFTSize::MAX as usize;
+
+        Self {
+            fft_in: utils::vec_with_capacity(fft_size, max_size, 0f32),
+            fft_out: utils::bins_vec(num_bins, Complex::new(0f32, 0f32)),
+            ifft_out: utils::vec_with_capacity(fft_size, max_size, 0f32),
+            spectrum_mag: utils::bins_vec(num_bins, 0f32),
+            spectrum_phase: utils::bins_vec(num_bins, 0f32),
+            spectrum_freq: utils::bins_vec(num_bins, 0f32),
+            spectrum_db: utils::bins_vec(num_bins, -120f32),
+            analyzer_input_db: utils::bins_vec(num_bins, -120f32),
+
+            prev_phase: utils::bins_vec(num_bins, 0f32),
+            spectrum_phase_advance: utils::bins_vec(num_bins, 0f32),
+            spectrum_inst_freq: utils::bins_vec(num_bins, 0f32),
+        }
+    }
+
+    pub fn fft_size_change(&mut self, new_fft_size: usize) {
+        let num_bins = fft_size_to_bins(new_fft_size);
+
+        self.fft_in.resize(new_fft_size, 0.0f32);
+        self.fft_out.resize(num_bins, Complex::new(0.0f32, 0.0f32));
+        self.ifft_out.resize(new_fft_size, 0.0f32);
+        self.spectrum_mag.resize(num_bins, 0.0f32);
+        self.spectrum_phase.resize(num_bins, 0.0f32);
+        self.spectrum_db.resize(num_bins, -120.0f32);
+        self.analyzer_input_db.resize(num_bins, -120.0f32);
+        self.spectrum_freq.resize(num_bins, 0.0f32);
+        self.prev_phase.resize(num_bins, 0.0f32);
+        self.spectrum_phase_advance.resize(num_bins, 0.0f32);
+        self.spectrum_inst_freq.resize(num_bins, 0.0f32);
+
+        self.fft_in.fill(0.0f32);
+        self.fft_out.fill(Complex::new(0.0f32, 0.0f32));
+        self.ifft_out.fill(0.0f32);
+        self.spectrum_mag.fill(0.0f32);
+        self.spectrum_phase.fill(0.0f32);
+        self.spectrum_db.fill(-120.0f32);
+        self.analyzer_input_db.fill(-120.0f32);
+        self.spectrum_freq.fill(0.0f32);
+        self.prev_phase.fill(0.0f32);
+        self.spectrum_phase_advance.fill(0.0f32);
+        self.spectrum_inst_freq.fill(0.0f32);
+    }
+
+    /// Fills `spectrum_freq` with the center frequency of every bin.
+    pub fn fill_freqs(&mut self, sample_rate: usize, fft_size: usize) {
+        for (i, freq) in self.spectrum_freq.iter_mut().enumerate() {
+            *freq = (i * sample_rate) as f32 / fft_size as f32;
+        }
+    }
+
+    /// Updates `spectrum_phase_advance` and `spectrum_inst_freq` of `bin` from the change in
+    /// `spectrum_phase` since the previous frame, which was `hop_size` samples earlier. The phase
+    /// advance is the bin's expected advance plus the deviation from it wrapped to -pi..pi, so it
+    /// can be accumulated directly for phase vocoder resynthesis.
+    pub fn track_phase(&mut self, bin: usize, fft_size: usize, hop_size: usize, sample_rate: usize) {
+        let expected_advance = TAU * (bin * hop_size) as f32 / fft_size as f32;
+        let phase = self.spectrum_phase[bin];
+        let deviation = utils::wrap_phase(phase - self.prev_phase[bin] - expected_advance);
+
+        self.prev_phase[bin] = phase;
+        self.spectrum_phase_advance[bin] = expected_advance + deviation;
+        self.spectrum_inst_freq[bin] = self.spectrum_phase_advance[bin] * sample_rate as f32 / (TAU * hop_size as f32);
+    }
+}
+
+/// `spectrum_db` of `bin` averaged over all `channels`.
+pub fn average_db(channels: &[FFTData], bin: usize) -> f32 {
+    channels.iter().map(|data| data.spectrum_db[bin]).sum::<f32>() / channels.len() as f32
+}
+
+/// `analyzer_input_db` of `bin` averaged over all `channels`.
+pub fn average_input_db(channels: &[FFTData], bin: usize) -> f32 {
+    channels.iter().map(|data| data.analyzer_input_db[bin]).sum::<f32>() / channels.len() as f32
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use nih_plug::prelude::Enum;
+use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
+
+use crate::fft_size::FFTSize;
+
+/// Forward and inverse plans for every `FFTSize`, planned up front so that switching sizes on the
+/// audio thread is just a different plan index. All plans share one scratch buffer that fits the
+/// largest of them.
+pub struct FFTPlans {
+    r2c: Vec<Arc<dyn RealToComplex<f32>>>,
+    c2r: Vec<Arc<dyn ComplexToReal<f32>>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+impl Default for FFTPlans {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FFTPlans {
+    pub fn new() -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let sizes = (0..FFTSize::variants().len()).map(|i| FFTSize::from_index(i) as usize);
+
+        let r2c: Vec<_> = sizes.clone().map(|size| planner.plan_fft_forward(size)).collect();
+        let c2r: Vec<_> = sizes.map(|size| planner.plan_fft_inverse(size)).collect();
+
+        let scratch_len = r2c
+            .iter()
+            .map(|plan| plan.get_scratch_len())
+            .chain(c2r.iter().map(|plan| plan.get_scratch_len()))
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            r2c,
+            c2r,
+            scratch: vec![Complex::new(0.0, 0.0); scratch_len],
+        }
+    }
+
+    /// Index of the plan for `fft_size`, to be passed to `forward` and `inverse`. Panics if
+    /// `fft_size` isn't one of the `FFTSize` variants.
+    pub fn plan_index(&self, fft_size: usize) -> usize {
+        self.r2c
+            .iter()
+            .position(|plan| plan.len() == fft_size)
+            .expect("FFT size has no plan")
+    }
+
+    /// `input` needs to be as long as the plan's FFT size and `output` `fft_size / 2 + 1`. `input`
+    /// is used as scratch space and gets overwritten.
+    pub fn forward(&mut self, plan: usize, input: &mut [f32], output: &mut [Complex<f32>]) {
+        let plan = &self.r2c[plan];
+        let scratch = &mut self.scratch[..plan.get_scratch_len()];
+        plan.process_with_scratch(input, output, scratch).unwrap();
+    }
+
+    /// `input` needs to be `fft_size / 2 + 1` long and `output` as long as the plan's FFT size.
+    /// `input` is used as scratch space and gets overwritten.
+    pub fn inverse(&mut self, plan: usize, input: &mut [Complex<f32>], output: &mut [f32]) {
+        // the imaginary parts of the DC and nyquist bins have to be zero for a real signal,
+        // realfft would zero them anyway but also return an error
+        input[0].im = 0.0;
+        if let Some(last) = input.last_mut() {
+            last.im = 0.0;
+        }
+
+        let plan = &self.c2r[plan];
+        let scratch = &mut self.scratch[..plan.get_scratch_len()];
+        plan.process_with_scratch(input, output, scratch).unwrap();
+    }
+}
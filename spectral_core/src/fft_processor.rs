@@ -0,0 +1,2601 @@
+use std::f32::consts::FRAC_PI_2;
+
+use realfft::{num_complex::Complex, num_traits::Zero};
+
+use crate::{
+    channel_mode::{self, ChannelMode},
+    fft_data::FFTData,
+    fft_plans::FFTPlans,
+    fft_size::FFTSize,
+    resolution::{self, BandSize, Resolution, MAX_BANDS, MAX_EFFECT_BANDS},
+    spectral_effect::{SpectralEffect, SpectrumValues},
+    transient::{self, TransientSwitch},
+    utils::{self, fft_size_to_bins},
+    window::{self, WindowType},
+};
+
+/// Most channels a processor can have, enough for 7.1.
+pub const MAX_CHANNELS: usize = 8;
+/// How long `set_bypass` takes to fade between the processed and the dry signal.
+pub const BYPASS_FADE_MS: f32 = 20.0;
+
+pub struct FFTProcessor<E: SpectralEffect> {
+    // there are two engines so that fft size, overlap and resolution changes can be crossfaded,
+    // `active` is the one that is heard once the crossfade is done
+    engines: [Engine; 2],
+    active: usize,
+    crossfade: Option<Crossfade>,
+    // settings that were requested while a crossfade was still running
+    pending: Option<Settings>,
+
+    window_type: WindowType,
+    kaiser_beta: f32,
+    channel_mode: ChannelMode,
+    mix: f32,
+    // how far the output has faded over to the dry signal, 1.0 when bypassed
+    bypass_gain: f32,
+    bypass_target: f32,
+    // low and high crossover frequency between the bands of a `Resolution`
+    crossovers: (f32, f32),
+    // fades between the regular bands and the transient band, shared by both engines
+    transient_switch: TransientSwitch,
+    // whether the analyzer dB values are calculated and `SpectralEffect::analyze` is called
+    analyzer_active: bool,
+
+    plans: FFTPlans,
+    sample_rate: usize,
+
+    pub fft_effect: E,
+}
+
+unsafe impl<E: SpectralEffect + Send> Send for FFTProcessor<E> {}
+unsafe impl<E: SpectralEffect + Sync> Sync for FFTProcessor<E> {}
+
+impl<E: SpectralEffect> FFTProcessor<E> {
+    /// Everything that depends on the FFT size is planned and allocated for the largest `FFTSize`
+    /// here, so that size changes can happen on the audio thread.
+    pub fn new(sample_rate: usize, num_channels: usize, fft_size: usize, overlap: usize, fft_effect: E) -> Self {
+        assert!(num_channels > 0 && num_channels <= MAX_CHANNELS);
+        let plans = FFTPlans::new();
+        let settings = Settings {
+            fft_size,
+            overlap,
+            zero_padding: 1,
+            resolution: Resolution::Single,
+            transients: false,
+        };
+
+        let mut processor = Self {
+            engines: [Engine::new(num_channels, settings, &plans), Engine::new(num_channels, settings, &plans)],
+            active: 0,
+            crossfade: None,
+            pending: None,
+
+            window_type: WindowType::Hann,
+            kaiser_beta: 0.0,
+            channel_mode: ChannelMode::Stereo,
+            mix: 1.0,
+            bypass_gain: 0.0,
+            bypass_target: 0.0,
+            crossovers: (resolution::DEFAULT_LOW_CROSSOVER_HZ, resolution::DEFAULT_HIGH_CROSSOVER_HZ),
+            transient_switch: TransientSwitch::new(sample_rate),
+            analyzer_active: true,
+
+            plans,
+            sample_rate,
+
+            fft_effect,
+        };
+
+        processor.fft_effect.set_num_channels(num_channels);
+        processor.update_effect();
+
+        processor
+    }
+
+    /// Same as `new`, but also buffers and transforms an aux input which is then passed to the
+    /// effect alongside the main input. The aux input always has as many channels as the main one,
+    /// see `process_sample_with_aux`.
+    pub fn new_with_aux(sample_rate: usize, num_channels: usize, fft_size: usize, overlap: usize, fft_effect: E) -> Self {
+        let mut processor = Self::new(sample_rate, num_channels, fft_size, overlap, fft_effect);
+        for engine in processor.engines.iter_mut() {
+            engine.enable_aux();
+        }
+
+        processor
+    }
+
+    /// Reallocates everything for `num_channels` channels and clears all buffers if the number of
+    /// channels changed. This allocates, call it from `initialize()`.
+    pub fn set_num_channels(&mut self, num_channels: usize) {
+        assert!(num_channels > 0 && num_channels <= MAX_CHANNELS);
+        if self.num_channels() == num_channels {
+            return;
+        }
+        self.cancel_crossfade();
+
+        let settings = self.settings();
+        let mix = self.wet_mix();
+        for engine in self.engines.iter_mut() {
+            let has_aux = engine.has_aux();
+            *engine = Engine::new(num_channels, settings, &self.plans);
+            engine.set_window(self.window_type, self.kaiser_beta);
+            engine.set_band_gains(self.sample_rate, self.crossovers);
+            if has_aux {
+                engine.enable_aux();
+            }
+            engine.set_mix(mix);
+        }
+        self.update_bypassed_channel();
+
+        self.fft_effect.set_num_channels(num_channels);
+        self.update_effect();
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.engines[self.active].num_channels()
+    }
+
+    pub fn set_sample_rate(&mut self, sr: usize) {
+        self.sample_rate = sr;
+        for engine in self.engines.iter_mut() {
+            engine.set_band_gains(sr, self.crossovers);
+        }
+        self.transient_switch.detector.set_sample_rate(sr);
+        self.fft_effect.set_sample_rate(sr, self.hop_size());
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.engines[self.active].main().fft_size
+    }
+
+    pub fn overlap(&self) -> usize {
+        self.engines[self.active].main().overlap
+    }
+
+    pub fn zero_padding(&self) -> usize {
+        self.engines[self.active].main().zero_padding
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.engines[self.active].resolution
+    }
+
+    /// Length of the main band's FFT including zero padding. This is the size effects see in
+    /// `analyze`, and the one its bins are spaced by.
+    pub fn transform_size(&self) -> usize {
+        self.engines[self.active].main().transform_size()
+    }
+
+    pub fn hop_size(&self) -> usize {
+        self.engines[self.active].main().hop_size()
+    }
+
+    /// The latency to report to the host, the window size of the longest band. While a crossfade
+    /// is running this is already the latency of the settings the processor is heading to.
+    pub fn latency_samples(&self) -> usize {
+        match self.pending {
+            Some(settings) => settings.latency(),
+            None => self.engines[self.active].latency(),
+        }
+    }
+
+    pub fn is_crossfading(&self) -> bool {
+        self.crossfade.is_some()
+    }
+
+    fn settings(&self) -> Settings {
+        self.engines[self.active].settings()
+    }
+
+    /// Changes the number of overlapping frames right away. Already overlap-added output is
+    /// discarded since it was scaled for the old hop size, use `crossfade_to` while playing.
+    pub fn change_overlap(&mut self, overlap: usize) {
+        self.cancel_crossfade();
+        if self.overlap() == overlap {
+            return;
+        }
+
+        self.engines[self.active].set_overlap(overlap);
+        self.update_effect();
+    }
+
+    /// Switches the analysis/synthesis window, and raises the overlap if the window needs it. Does
+    /// nothing if neither the window nor (for kaiser) beta changed, so it's fine to call this every
+    /// block.
+    pub fn set_window(&mut self, window_type: WindowType, kaiser_beta: f32) {
+        if self.window_type == window_type && (window_type != WindowType::Kaiser || self.kaiser_beta == kaiser_beta) {
+            return;
+        }
+        self.window_type = window_type;
+        self.kaiser_beta = kaiser_beta;
+
+        for engine in self.engines.iter_mut() {
+            engine.set_window(window_type, kaiser_beta);
+        }
+        // the hop size changes with windows that need more overlap, see `WindowType::min_overlap`
+        self.update_effect();
+    }
+
+    /// Dry/wet mix between 0.0 (dry) and 1.0 (wet). The dry signal is delayed by the latency, so
+    /// it lines up with the processed one and the mix is phase coherent for every effect.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+        self.update_mix();
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix
+    }
+
+    /// Fades over to the dry signal within `BYPASS_FADE_MS`, or back. The dry signal is the same
+    /// latency compensated one as for the mix, so unlike the host's bypass this doesn't jump in
+    /// time. Everything keeps running while bypassed, so coming back is seamless as well.
+    pub fn set_bypass(&mut self, bypass: bool) {
+        self.bypass_target = if bypass { 1.0 } else { 0.0 };
+    }
+
+    /// Whether the output is entirely the dry signal.
+    pub fn is_bypassed(&self) -> bool {
+        self.bypass_gain == 1.0
+    }
+
+    fn is_bypass_fading(&self) -> bool {
+        self.bypass_gain != self.bypass_target
+    }
+
+    /// Moves the bypass fade one sample further.
+    fn next_bypass_gain(&mut self) {
+        let step = 1000.0 / (BYPASS_FADE_MS * self.sample_rate as f32);
+        self.bypass_gain = if self.bypass_target > self.bypass_gain {
+            (self.bypass_gain + step).min(self.bypass_target)
+        } else {
+            (self.bypass_gain - step).max(self.bypass_target)
+        };
+        self.update_mix();
+    }
+
+    /// The mix the engines get, the bypass fade turns it down on top of the mix setting.
+    fn wet_mix(&self) -> f32 {
+        self.mix * (1.0 - self.bypass_gain)
+    }
+
+    fn update_mix(&mut self) {
+        let mix = self.wet_mix();
+        for engine in self.engines.iter_mut() {
+            engine.set_mix(mix);
+        }
+    }
+
+    /// Switches between left/right and mid/side processing, see `ChannelMode`. Only has an effect
+    /// with two channels. The already buffered signal is converted as well, so switching doesn't
+    /// click and it's fine to call this every block. Also applies to the aux input if it's stereo.
+    pub fn set_channel_mode(&mut self, channel_mode: ChannelMode) {
+        if self.channel_mode == channel_mode {
+            return;
+        }
+        let was_mid_side = self.is_mid_side();
+        self.channel_mode = channel_mode;
+
+        if was_mid_side != self.is_mid_side() {
+            for engine in self.engines.iter_mut() {
+                engine.convert_mid_side(was_mid_side);
+            }
+        }
+        self.update_bypassed_channel();
+    }
+
+    pub fn channel_mode(&self) -> ChannelMode {
+        self.channel_mode
+    }
+
+    fn is_mid_side(&self) -> bool {
+        self.num_channels() == 2 && self.channel_mode.is_mid_side()
+    }
+
+    fn update_bypassed_channel(&mut self) {
+        let bypassed_channel = match self.num_channels() {
+            2 => self.channel_mode.bypassed_channel(),
+            _ => None,
+        };
+        for engine in self.engines.iter_mut() {
+            engine.set_bypassed_channel(bypassed_channel);
+        }
+    }
+
+    /// Crossover frequencies between the bands of a `Resolution` with more than one band, the high
+    /// one is only used by `Resolution::Triple`. The band gains are only recalculated when they
+    /// change, so it's fine to call this every block.
+    pub fn set_crossovers(&mut self, low_hz: f32, high_hz: f32) {
+        let crossovers = (low_hz.min(high_hz), low_hz.max(high_hz));
+        if self.crossovers == crossovers {
+            return;
+        }
+        self.crossovers = crossovers;
+
+        for engine in self.engines.iter_mut() {
+            engine.set_band_gains(self.sample_rate, crossovers);
+        }
+    }
+
+    pub fn crossovers(&self) -> (f32, f32) {
+        self.crossovers
+    }
+
+    /// Runs an extra band with an eighth of the FFT size next to the regular ones and switches over
+    /// to it around strong onsets, so drums keep their attack instead of getting pre-echo and
+    /// smearing from the long windows. The switch starts as soon as an onset comes in, which is a
+    /// full latency before its output is due, and lasts until the long windows no longer contain
+    /// it. Costs about as much CPU as another band of that size. Turning it on or off crossfades
+    /// like `crossfade_to`, so it's fine to call this every block.
+    pub fn set_transient_switching(&mut self, enabled: bool) {
+        let target = self.pending.unwrap_or(self.settings());
+        if target.transients == enabled {
+            return;
+        }
+
+        self.start_crossfade(Settings {
+            transients: enabled,
+            ..target
+        });
+    }
+
+    pub fn transient_switching(&self) -> bool {
+        self.settings().transients
+    }
+
+    /// How many dB a fast envelope of the input has to jump above a slow one to count as an onset.
+    pub fn set_transient_threshold(&mut self, threshold_db: f32) {
+        self.transient_switch.detector.set_threshold(threshold_db);
+    }
+
+    /// Turns the analyzer off, e.g. while the editor is closed. The input and output spectra are
+    /// then no longer converted to dB for it and the effect's `analyze` isn't called, which saves a
+    /// magnitude and a log per bin, channel and hop for both of them.
+    pub fn set_analyzer_active(&mut self, analyzer_active: bool) {
+        self.analyzer_active = analyzer_active;
+    }
+
+    pub fn analyzer_active(&self) -> bool {
+        self.analyzer_active
+    }
+
+    /// Changes the FFT size right away and clears all buffers, use `crossfade_to` while playing.
+    /// Doesn't allocate, all buffers and plans already fit the largest `FFTSize`. The effect's
+    /// `resize` should stick to that as well.
+    pub fn change_fft_size(&mut self, new_size: usize) {
+        self.cancel_crossfade();
+        let settings = Settings {
+            fft_size: new_size,
+            ..self.settings()
+        };
+        self.reconfigure(settings);
+    }
+
+    /// Changes the zero padding factor right away and clears all buffers, use `crossfade_to` while
+    /// playing. See `ZeroPadding`.
+    pub fn change_zero_padding(&mut self, zero_padding: usize) {
+        self.cancel_crossfade();
+        if self.zero_padding() == zero_padding {
+            return;
+        }
+
+        let settings = Settings {
+            zero_padding,
+            ..self.settings()
+        };
+        self.reconfigure(settings);
+    }
+
+    /// Changes the number of bands right away and clears all buffers, use `crossfade_to_resolution`
+    /// while playing. See `Resolution`.
+    pub fn change_resolution(&mut self, resolution: Resolution) {
+        self.cancel_crossfade();
+        if self.resolution() == resolution {
+            return;
+        }
+
+        let settings = Settings {
+            resolution,
+            ..self.settings()
+        };
+        self.reconfigure(settings);
+    }
+
+    fn reconfigure(&mut self, settings: Settings) {
+        let engine = &mut self.engines[self.active];
+        engine.configure(settings, &self.plans, self.window_type, self.kaiser_beta);
+        engine.set_band_gains(self.sample_rate, self.crossovers);
+
+        self.update_effect();
+    }
+
+    /// Click-free version of `change_fft_size`, `change_overlap` and `change_zero_padding`. The new
+    /// settings run in the second engine, which gets the input history of the current one. Once it
+    /// has produced complete output it's crossfaded in with an equal-power fade over its latency. A
+    /// change requested while a crossfade is running starts once that one is done.
+    ///
+    /// The resolution stays what it is (or what it's heading to), `Resolution::Single` unless it was
+    /// changed, so effects with a single band never have to pass it. See `crossfade_to_resolution`.
+    ///
+    /// During the crossfade the effect processes both sizes, `resize` is called with the larger
+    /// transform size until it's done.
+    pub fn crossfade_to(&mut self, fft_size: usize, overlap: usize, zero_padding: usize) {
+        let target = self.pending.unwrap_or(self.settings());
+        self.crossfade_to_resolution(fft_size, overlap, zero_padding, target.resolution);
+    }
+
+    /// Same as `crossfade_to`, but also changes the number of bands, the click-free version of
+    /// `change_resolution`.
+    pub fn crossfade_to_resolution(&mut self, fft_size: usize, overlap: usize, zero_padding: usize, resolution: Resolution) {
+        let target = self.pending.unwrap_or(self.settings());
+        self.start_crossfade(Settings {
+            fft_size,
+            overlap,
+            zero_padding,
+            resolution,
+            transients: target.transients,
+        });
+    }
+
+    fn start_crossfade(&mut self, settings: Settings) {
+        if self.crossfade.is_some() {
+            self.pending = Some(settings);
+            return;
+        }
+        if self.settings() == settings {
+            return;
+        }
+
+        let [first, second] = &mut self.engines;
+        let (outgoing, incoming) = if self.active == 0 { (first, second) } else { (second, first) };
+        incoming.configure(settings, &self.plans, self.window_type, self.kaiser_beta);
+        incoming.set_band_gains(self.sample_rate, self.crossovers);
+        let warmup = incoming.prefill_from(outgoing);
+        if settings.transients && !outgoing.transients {
+            self.transient_switch.reset();
+        }
+        self.active = 1 - self.active;
+
+        self.crossfade = Some(Crossfade {
+            warmup,
+            length: settings.latency(),
+            progress: 0,
+        });
+        self.fft_effect.crossfade_started();
+        self.update_effect();
+    }
+
+    /// Clears every buffer, the transient switch and the effect's state, so nothing from before a
+    /// transport jump is heard afterwards. A running crossfade or bypass fade is finished right
+    /// away. Doesn't allocate, so it can be called from the audio thread.
+    pub fn reset(&mut self) {
+        let settings = self.pending.take().unwrap_or(self.settings());
+        self.crossfade = None;
+
+        let engine = &mut self.engines[self.active];
+        engine.configure(settings, &self.plans, self.window_type, self.kaiser_beta);
+        engine.set_band_gains(self.sample_rate, self.crossovers);
+        self.transient_switch.reset();
+        self.bypass_gain = self.bypass_target;
+        self.update_mix();
+
+        self.update_effect();
+        self.fft_effect.reset();
+    }
+
+    fn cancel_crossfade(&mut self) {
+        self.pending = None;
+        if self.crossfade.take().is_some() {
+            self.update_effect();
+        }
+    }
+
+    fn finish_crossfade(&mut self) {
+        self.crossfade = None;
+        self.update_effect();
+
+        if let Some(settings) = self.pending.take() {
+            self.start_crossfade(settings);
+        }
+    }
+
+    /// Tells the effect and the transient switch about the sizes of the active engine. While
+    /// crossfading `resize` gets the largest transform size of both engines and `set_bands` gets the
+    /// outgoing engine's bands as well, after `MAX_BANDS`.
+    fn update_effect(&mut self) {
+        // the outgoing engine's bands are separate bands to the effect, so it keeps their state apart
+        let [first, second] = &mut self.engines;
+        let (outgoing, engine) = if self.active == 0 { (second, first) } else { (first, second) };
+        engine.set_band_offset(0);
+        outgoing.set_band_offset(MAX_BANDS);
+
+        self.transient_switch
+            .set_lengths(transient::transient_fft_size(engine.main().fft_size), engine.latency());
+
+        let mut transform_size = engine.max_transform_size();
+        if self.crossfade.is_some() {
+            transform_size = transform_size.max(outgoing.max_transform_size());
+        }
+        self.fft_effect.resize(transform_size);
+        self.fft_effect.set_sample_rate(self.sample_rate, engine.main().hop_size());
+
+        let mut bands = [BandSize::default(); MAX_EFFECT_BANDS];
+        for (band, stft) in bands.iter_mut().zip(engine.bands()) {
+            *band = BandSize {
+                transform_size: stft.transform_size(),
+                hop_size: stft.hop_size(),
+            };
+        }
+        let mut num_bands = engine.bands().len();
+        if self.crossfade.is_some() {
+            for (band, stft) in bands[MAX_BANDS..].iter_mut().zip(outgoing.bands()) {
+                *band = BandSize {
+                    transform_size: stft.transform_size(),
+                    hop_size: stft.hop_size(),
+                };
+            }
+            num_bands = MAX_BANDS + outgoing.bands().len();
+        }
+        self.fft_effect.set_bands(&bands[..num_bands]);
+    }
+
+    /// Processes one sample of every channel in place, `samples` needs to have one sample per
+    /// channel.
+    pub fn process_sample(&mut self, samples: &mut [f32]) {
+        self.process(samples, None)
+    }
+
+    /// Only does anything with `aux_samples` if the processor was created with `new_with_aux`. If
+    /// the aux input has fewer channels than the main input, the remaining channels get its last
+    /// channel, so a mono sidechain is used for every channel.
+    pub fn process_sample_with_aux(&mut self, samples: &mut [f32], aux_samples: &[f32]) {
+        self.process(samples, Some(aux_samples))
+    }
+
+    /// Processes a block of every channel in place, `channels` needs to have one slice per channel
+    /// and all of them the same length. Whole runs of samples up to the next hop are copied at once,
+    /// which gives the same output as `process_sample` with a lot less work per sample.
+    ///
+    /// Settings changed between calls apply from the first sample of the next block on, and to the
+    /// effect from the next hop on. With `SAMPLE_ACCURATE_AUTOMATION` nih_plug splits the host's
+    /// buffer at every parameter change, so the output doesn't depend on the host's block size.
+    pub fn process_block(&mut self, channels: &mut [&mut [f32]]) {
+        self.process_block_inner(channels, None)
+    }
+
+    /// Block version of `process_sample_with_aux`. `aux_channels` need to be as long as `channels`
+    /// and are used as scratch space in the mid/side `ChannelMode`s.
+    pub fn process_block_with_aux(&mut self, channels: &mut [&mut [f32]], aux_channels: &mut [&mut [f32]]) {
+        self.process_block_inner(channels, Some(aux_channels))
+    }
+
+    fn process_block_inner(&mut self, channels: &mut [&mut [f32]], mut aux_channels: Option<&mut [&mut [f32]]>) {
+        let num_channels = channels.len();
+        debug_assert_eq!(num_channels, self.num_channels());
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+
+        // both engines need every input sample while crossfading and the mix changes with every
+        // sample while the bypass fades, that's rare enough to just go sample by sample until it's done
+        let mut start = 0;
+        while (self.crossfade.is_some() || self.is_bypass_fading()) && start < num_samples {
+            let mut samples = [0f32; MAX_CHANNELS];
+            for (sample, channel) in samples.iter_mut().zip(channels.iter()) {
+                *sample = channel[start];
+            }
+            let mut aux_samples = [0f32; MAX_CHANNELS];
+            let aux_samples = aux_channels.as_deref().map(|aux_channels| {
+                let aux_samples = &mut aux_samples[..aux_channels.len().min(MAX_CHANNELS)];
+                for (sample, channel) in aux_samples.iter_mut().zip(aux_channels.iter()) {
+                    *sample = channel[start];
+                }
+                &*aux_samples
+            });
+
+            self.process(&mut samples[..num_channels], aux_samples);
+
+            for (sample, channel) in samples.iter().zip(channels.iter_mut()) {
+                channel[start] = *sample;
+            }
+            start += 1;
+        }
+        if start == num_samples {
+            return;
+        }
+
+        let mid_side = self.is_mid_side();
+        if mid_side {
+            channel_mode::encode_mid_side_block(channels, start);
+            if let Some(aux_channels) = aux_channels.as_deref_mut() {
+                // a mono sidechain is used as it is for both mid and side
+                channel_mode::encode_mid_side_block(aux_channels, start);
+            }
+        }
+
+        if self.engines[self.active].transients {
+            self.process_block_with_transients(channels, aux_channels, start);
+        } else {
+            self.engines[self.active].process_block(
+                channels,
+                aux_channels.as_deref(),
+                start,
+                &mut self.plans,
+                &mut self.fft_effect,
+                self.sample_rate,
+                self.analyzer_active,
+            );
+        }
+
+        if mid_side {
+            channel_mode::decode_mid_side_block(channels, start);
+        }
+    }
+
+    /// The active engine's `process_block` with the transient band's gain following the switch.
+    /// Chunks in which the gain doesn't change are still processed as a block, while it fades they
+    /// go sample by sample.
+    fn process_block_with_transients(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        mut aux_channels: Option<&mut [&mut [f32]]>,
+        start: usize,
+    ) {
+        const CHUNK: usize = 64;
+        let num_channels = channels.len();
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+        let engine = &mut self.engines[self.active];
+        let mut offset = start;
+        while offset < num_samples {
+            let block = offset..num_samples.min(offset + CHUNK);
+
+            let mut gains = [0f32; CHUNK];
+            for (gain, i) in gains.iter_mut().zip(block.clone()) {
+                let level = channels.iter().fold(0f32, |level, channel| level.max(channel[i].abs()));
+                *gain = self.transient_switch.next_gain(level);
+            }
+            let gains = &gains[..block.len()];
+
+            if gains.iter().all(|&gain| gain == gains[0]) {
+                engine.set_transient_gain(gains[0]);
+
+                let mut block_channels: [&mut [f32]; MAX_CHANNELS] = Default::default();
+                for (block_channel, channel) in block_channels.iter_mut().zip(channels.iter_mut()) {
+                    *block_channel = &mut channel[block.clone()];
+                }
+                let mut aux_block_channels: [&mut [f32]; MAX_CHANNELS] = Default::default();
+                let has_aux = aux_channels.is_some();
+                let mut num_aux_channels = 0;
+                if let Some(aux_channels) = aux_channels.as_deref_mut() {
+                    num_aux_channels = aux_channels.len().min(MAX_CHANNELS);
+                    for (block_channel, channel) in aux_block_channels.iter_mut().zip(aux_channels.iter_mut()) {
+                        *block_channel = &mut channel[block.clone()];
+                    }
+                }
+
+                engine.process_block(
+                    &mut block_channels[..num_channels],
+                    has_aux.then_some(&aux_block_channels[..num_aux_channels]),
+                    0,
+                    &mut self.plans,
+                    &mut self.fft_effect,
+                    self.sample_rate,
+                    self.analyzer_active,
+                );
+            } else {
+                for (gain, i) in gains.iter().zip(block.clone()) {
+                    engine.set_transient_gain(*gain);
+
+                    let mut samples = [0f32; MAX_CHANNELS];
+                    for (sample, channel) in samples.iter_mut().zip(channels.iter()) {
+                        *sample = channel[i];
+                    }
+                    let mut aux_samples = [0f32; MAX_CHANNELS];
+                    let aux_samples = aux_channels.as_deref().map(|aux_channels| {
+                        let aux_samples = &mut aux_samples[..aux_channels.len().min(MAX_CHANNELS)];
+                        for (sample, channel) in aux_samples.iter_mut().zip(aux_channels.iter()) {
+                            *sample = channel[i];
+                        }
+                        &*aux_samples
+                    });
+
+                    let mut output = [0f32; MAX_CHANNELS];
+                    engine.process_sample(
+                        &samples[..num_channels],
+                        aux_samples,
+                        &mut output[..num_channels],
+                        &mut self.plans,
+                        &mut self.fft_effect,
+                        self.sample_rate,
+                        self.analyzer_active,
+                    );
+                    for (sample, channel) in output.iter().zip(channels.iter_mut()) {
+                        channel[i] = *sample;
+                    }
+                }
+            }
+            offset = block.end;
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32], aux_samples: Option<&[f32]>) {
+        let num_channels = samples.len();
+        debug_assert_eq!(num_channels, self.num_channels());
+        let mid_side = self.is_mid_side();
+        if self.is_bypass_fading() {
+            self.next_bypass_gain();
+        }
+
+        let mut input = [0f32; MAX_CHANNELS];
+        input[..num_channels].copy_from_slice(samples);
+        if let (true, [left, right, ..]) = (mid_side, &mut input) {
+            channel_mode::encode_mid_side(left, right);
+        }
+
+        let mut aux_input = [0f32; MAX_CHANNELS];
+        let aux_samples = aux_samples.map(|aux_samples| {
+            let aux_input = &mut aux_input[..aux_samples.len().min(MAX_CHANNELS)];
+            aux_input.copy_from_slice(&aux_samples[..aux_input.len()]);
+            // a mono sidechain is used as it is for both mid and side
+            if let (true, [left, right]) = (mid_side, &mut *aux_input) {
+                channel_mode::encode_mid_side(left, right);
+            }
+            &*aux_input
+        });
+
+        // the outgoing engine might still need the switch while transient switching is turned off
+        let outgoing = &self.engines[1 - self.active];
+        if self.engines[self.active].transients || (self.crossfade.is_some() && outgoing.transients) {
+            let level = input[..num_channels].iter().fold(0f32, |level, sample| level.max(sample.abs()));
+            let gain = self.transient_switch.next_gain(level);
+            for engine in self.engines.iter_mut() {
+                engine.set_transient_gain(gain);
+            }
+        }
+
+        self.engines[self.active].process_sample(
+            &input[..num_channels],
+            aux_samples,
+            samples,
+            &mut self.plans,
+            &mut self.fft_effect,
+            self.sample_rate,
+            self.analyzer_active,
+        );
+
+        if let Some(crossfade) = &mut self.crossfade {
+            // the outgoing engine keeps running until it's faded out, only the incoming one feeds the analyzer
+            let mut faded_output = [0f32; MAX_CHANNELS];
+            self.engines[1 - self.active].process_sample(
+                &input[..num_channels],
+                aux_samples,
+                &mut faded_output[..num_channels],
+                &mut self.plans,
+                &mut self.fft_effect,
+                self.sample_rate,
+                false,
+            );
+            let (gain_in, gain_out) = crossfade.next_gains();
+            if crossfade.is_done() {
+                self.finish_crossfade();
+            }
+
+            for (sample, faded) in samples.iter_mut().zip(faded_output) {
+                *sample = *sample * gain_in + faded * gain_out;
+            }
+        }
+
+        if let (true, [mid, side]) = (mid_side, &mut *samples) {
+            channel_mode::decode_mid_side(mid, side);
+        }
+    }
+
+    /// Runs one hop of the active engine's main band right away.
+    pub fn process_windows(&mut self) {
+        self.engines[self.active].main_mut().process_windows(
+            &mut self.plans,
+            &mut self.fft_effect,
+            self.sample_rate,
+            self.analyzer_active,
+        );
+    }
+}
+
+/// Progress of a crossfade between the two engines. Until `warmup` runs out only the outgoing
+/// engine is heard.
+struct Crossfade {
+    warmup: usize,
+    length: usize,
+    progress: usize,
+}
+
+impl Crossfade {
+    /// Equal-power gains for the incoming and the outgoing engine at the current sample, advances
+    /// the crossfade by one sample.
+    fn next_gains(&mut self) -> (f32, f32) {
+        if self.warmup > 0 {
+            self.warmup -= 1;
+            return (0.0, 1.0);
+        }
+
+        let angle = self.progress as f32 / self.length as f32 * FRAC_PI_2;
+        self.progress += 1;
+        (angle.sin(), angle.cos())
+    }
+
+    fn is_done(&self) -> bool {
+        self.warmup == 0 && self.progress >= self.length
+    }
+}
+
+/// Everything the two engines can be crossfaded between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Settings {
+    fft_size: usize,
+    overlap: usize,
+    zero_padding: usize,
+    resolution: Resolution,
+    transients: bool,
+}
+
+impl Settings {
+    fn latency(&self) -> usize {
+        self.resolution.latency(self.fft_size)
+    }
+}
+
+/// One STFT for every band of a `Resolution`, all of them fed the same input. Each band is delayed
+/// to the latency of the longest one before they're summed, and the longest one also carries the
+/// dry signal. With transient switching the band right after them is the transient band, which
+/// covers every frequency and is faded in instead of the others around onsets.
+struct Engine {
+    // always `MAX_BANDS` of them so that changing the resolution doesn't allocate, only the first
+    // `resolution.num_bands()` are used, plus the transient band
+    bands: Vec<STFTEngine>,
+    resolution: Resolution,
+    transients: bool,
+    mix: f32,
+    // how much of the transient band is heard, see `TransientSwitch`
+    transient_gain: f32,
+}
+
+impl Engine {
+    fn new(num_channels: usize, settings: Settings, plans: &FFTPlans) -> Self {
+        let plan = plans.plan_index(transform_size(settings.fft_size, settings.zero_padding));
+        let mut engine = Self {
+            bands: (0..MAX_BANDS)
+                .map(|band| {
+                    STFTEngine::new(num_channels, settings.fft_size, settings.overlap, settings.zero_padding, plan, band)
+                })
+                .collect(),
+            resolution: settings.resolution,
+            transients: settings.transients,
+            mix: 1.0,
+            transient_gain: 0.0,
+        };
+        engine.configure(settings, plans, WindowType::Hann, 0.0);
+
+        engine
+    }
+
+    fn bands(&self) -> &[STFTEngine] {
+        &self.bands[..self.num_bands()]
+    }
+
+    fn bands_mut(&mut self) -> &mut [STFTEngine] {
+        let num_bands = self.num_bands();
+        &mut self.bands[..num_bands]
+    }
+
+    fn num_bands(&self) -> usize {
+        self.resolution.num_bands() + self.transients as usize
+    }
+
+    /// The band at the FFT size the engine was configured with.
+    fn main(&self) -> &STFTEngine {
+        &self.bands[0]
+    }
+
+    fn main_mut(&mut self) -> &mut STFTEngine {
+        &mut self.bands[0]
+    }
+
+    fn settings(&self) -> Settings {
+        let main = self.main();
+        Settings {
+            fft_size: main.fft_size,
+            overlap: main.overlap,
+            zero_padding: main.zero_padding,
+            resolution: self.resolution,
+            transients: self.transients,
+        }
+    }
+
+    fn latency(&self) -> usize {
+        self.settings().latency()
+    }
+
+    fn max_transform_size(&self) -> usize {
+        self.bands().iter().map(STFTEngine::transform_size).max().unwrap_or(0)
+    }
+
+    fn num_channels(&self) -> usize {
+        self.main().input_buffer.len()
+    }
+
+    fn has_aux(&self) -> bool {
+        self.main().aux_buffer.is_some()
+    }
+
+    fn enable_aux(&mut self) {
+        for stft in self.bands.iter_mut() {
+            stft.enable_aux();
+        }
+    }
+
+    /// Resizes every band for `settings` and clears all buffers. The band gains need to be set
+    /// again afterwards.
+    fn configure(&mut self, settings: Settings, plans: &FFTPlans, window_type: WindowType, kaiser_beta: f32) {
+        self.resolution = settings.resolution;
+        self.transients = settings.transients;
+        self.transient_gain = 0.0;
+        let latency = settings.latency();
+        let num_resolution_bands = settings.resolution.num_bands();
+        for (band, stft) in self.bands_mut().iter_mut().enumerate() {
+            let fft_size = if band < num_resolution_bands {
+                Resolution::band_fft_size(settings.fft_size, band)
+            } else {
+                transient::transient_fft_size(settings.fft_size)
+            };
+            let plan = plans.plan_index(transform_size(fft_size, settings.zero_padding));
+            stft.delay = latency - fft_size;
+            stft.configure(fft_size, settings.overlap, settings.zero_padding, plan, window_type, kaiser_beta);
+        }
+        self.set_mix(self.mix);
+    }
+
+    /// The band gains depend on the transform sizes, the sample rate and the crossovers.
+    fn set_band_gains(&mut self, sample_rate: usize, crossovers: (f32, f32)) {
+        let resolution = self.resolution;
+        for stft in self.bands_mut() {
+            stft.set_band_gain(resolution, sample_rate, crossovers);
+        }
+    }
+
+    fn set_overlap(&mut self, overlap: usize) {
+        for stft in self.bands_mut() {
+            stft.set_overlap(overlap);
+        }
+    }
+
+    fn set_window(&mut self, window_type: WindowType, kaiser_beta: f32) {
+        for stft in self.bands_mut() {
+            stft.set_window(window_type, kaiser_beta);
+        }
+    }
+
+    /// Added to the index of every band that's passed to `SpectralEffect::set_band`.
+    fn set_band_offset(&mut self, band_offset: usize) {
+        for stft in self.bands.iter_mut() {
+            stft.band_offset = band_offset;
+        }
+    }
+
+    fn set_bypassed_channel(&mut self, bypassed_channel: Option<usize>) {
+        for stft in self.bands.iter_mut() {
+            stft.bypassed_channel = bypassed_channel;
+        }
+    }
+
+    fn convert_mid_side(&mut self, was_mid_side: bool) {
+        for stft in self.bands_mut() {
+            stft.convert_mid_side(was_mid_side);
+        }
+    }
+
+    /// Only the longest band's input is delayed by the full latency, so the dry signal comes from
+    /// that one. The transient gain only fades the wet signal.
+    fn set_mix(&mut self, mix: f32) {
+        self.mix = mix;
+        let latency = self.latency();
+        let num_resolution_bands = self.resolution.num_bands();
+        let transient_gain = self.transient_gain;
+        let mut has_dry = false;
+        for (band, stft) in self.bands_mut().iter_mut().enumerate() {
+            let carries_dry = !has_dry && stft.fft_size == latency;
+            has_dry |= carries_dry;
+            stft.dry_gain = if carries_dry { 1.0 - mix } else { 0.0 };
+            stft.wet_gain = if band >= num_resolution_bands {
+                mix * transient_gain
+            } else {
+                mix * (1.0 - transient_gain)
+            };
+        }
+    }
+
+    fn set_transient_gain(&mut self, gain: f32) {
+        if !self.transients || self.transient_gain == gain {
+            return;
+        }
+        self.transient_gain = gain;
+        self.set_mix(self.mix);
+    }
+
+    /// Prefills every band from the longest band of `other`, which has the most history. Returns
+    /// how many samples it takes until the summed output is complete.
+    fn prefill_from(&mut self, other: &Engine) -> usize {
+        let source = other.bands().iter().max_by_key(|stft| stft.fft_size).unwrap_or(other.main());
+        self.bands_mut()
+            .iter_mut()
+            .map(|stft| {
+                // every frame overlapping the output needs a full window of input, and the band's
+                // output is delayed on top of that
+                let history = stft.prefill_from(source);
+                2 * stft.fft_size - history + stft.delay
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// `STFTEngine::process_block` for every band. A single band works right in `channels`, with
+    /// more of them each band processes a copy of the input in short chunks that are summed after.
+    /// Only the main band feeds the analyzer, if `analyze` is set.
+    #[allow(clippy::too_many_arguments)]
+    fn process_block<E: SpectralEffect>(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        aux_channels: Option<&[&mut [f32]]>,
+        start: usize,
+        plans: &mut FFTPlans,
+        fft_effect: &mut E,
+        sample_rate: usize,
+        analyze: bool,
+    ) {
+        if self.num_bands() == 1 {
+            self.bands[0].process_block(channels, aux_channels, start, plans, fft_effect, sample_rate, analyze);
+            return;
+        }
+
+        const CHUNK: usize = 64;
+        let num_channels = channels.len();
+        let num_aux_channels = aux_channels.map_or(0, |aux_channels| aux_channels.len().min(MAX_CHANNELS));
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+        let mut offset = start;
+        while offset < num_samples {
+            let block = offset..num_samples.min(offset + CHUNK);
+            let len = block.len();
+
+            let mut aux_chunk = [[0f32; CHUNK]; MAX_CHANNELS];
+            if let Some(aux_channels) = aux_channels {
+                for (chunk, channel) in aux_chunk.iter_mut().zip(aux_channels.iter()) {
+                    chunk[..len].copy_from_slice(&channel[block.clone()]);
+                }
+            }
+
+            let mut sum = [[0f32; CHUNK]; MAX_CHANNELS];
+            for (band, stft) in self.bands_mut().iter_mut().enumerate() {
+                let mut chunk = [[0f32; CHUNK]; MAX_CHANNELS];
+                for (chunk, channel) in chunk.iter_mut().zip(channels.iter()) {
+                    chunk[..len].copy_from_slice(&channel[block.clone()]);
+                }
+                let mut chunk_channels = chunk.each_mut().map(|chunk| &mut chunk[..len]);
+                let aux_chunk_channels = aux_chunk.each_mut().map(|chunk| &mut chunk[..len]);
+
+                stft.process_block(
+                    &mut chunk_channels[..num_channels],
+                    aux_channels.map(|_| &aux_chunk_channels[..num_aux_channels]),
+                    0,
+                    plans,
+                    fft_effect,
+                    sample_rate,
+                    analyze && band == 0,
+                );
+
+                for (sum, chunk) in sum.iter_mut().zip(chunk_channels.iter()) {
+                    for (sum, sample) in sum.iter_mut().zip(chunk.iter()) {
+                        *sum += sample;
+                    }
+                }
+            }
+
+            for (channel, sum) in channels.iter_mut().zip(sum.iter()) {
+                channel[block.clone()].copy_from_slice(&sum[..len]);
+            }
+            offset += len;
+        }
+    }
+
+    /// `STFTEngine::process_sample` for every band, only the main band feeds the analyzer.
+    #[allow(clippy::too_many_arguments)]
+    fn process_sample<E: SpectralEffect>(
+        &mut self,
+        samples: &[f32],
+        aux_samples: Option<&[f32]>,
+        output: &mut [f32],
+        plans: &mut FFTPlans,
+        fft_effect: &mut E,
+        sample_rate: usize,
+        analyze: bool,
+    ) {
+        let Some((main, others)) = self.bands_mut().split_first_mut() else {
+            return;
+        };
+        main.process_sample(samples, aux_samples, output, plans, fft_effect, sample_rate, analyze);
+
+        for stft in others {
+            let mut band_output = [0f32; MAX_CHANNELS];
+            let band_output = &mut band_output[..output.len()];
+            stft.process_sample(samples, aux_samples, band_output, plans, fft_effect, sample_rate, false);
+            for (sample, band_sample) in output.iter_mut().zip(band_output.iter()) {
+                *sample += band_sample;
+            }
+        }
+    }
+}
+
+/// Buffers, window and spectra of a single STFT running at one FFT size and overlap.
+struct STFTEngine {
+    input_buffer: Vec<Vec<f32>>,
+    aux_buffer: Option<Vec<Vec<f32>>>,
+    // `delay` samples longer than the window, so the band's output lines up with the longest band
+    output_buffer: Vec<Vec<f32>>,
+    out_pos: usize,
+    delay: usize,
+
+    window: Vec<f32>,
+    // undoes the window's coherent gain and the one sided spectrum in the forward fft
+    analysis_gain: f32,
+
+    pos: usize,
+    count_to_next_hop: usize,
+
+    // the requested overlap, the hop size uses at least the window's `WindowType::min_overlap`
+    overlap: usize,
+    min_overlap: usize,
+    // the window the output is multiplied with before it's overlap-added, scaled so that the output
+    // is at unity gain at every sample, see `utils::fill_synthesis_window`
+    synthesis_window: Vec<f32>,
+
+    // the transform is `zero_padding` times longer than the window
+    zero_padding: usize,
+    plan: usize,
+    data: Vec<FFTData>,
+    aux_data: Option<Vec<FFTData>>,
+    ifft_in: Vec<Vec<Complex<f32>>>,
+    // channel that is resynthesized from its unprocessed spectrum, see `ChannelMode`
+    bypassed_channel: Option<usize>,
+    // index of the band within its `Resolution` and the gain of every bin within that band, empty
+    // if it's the only band
+    band: usize,
+    band_gain: Vec<f32>,
+    // `MAX_BANDS` for the outgoing engine of a crossfade, see `SpectralEffect::crossfade_started`
+    band_offset: usize,
+    // sample rate `spectrum_freq` was filled for, zero once the transform size changed
+    freqs_sample_rate: usize,
+    // the sample that gets overwritten in `input_buffer` is exactly `fft_size` samples old, so it's
+    // the dry signal delayed by the window size
+    dry_gain: f32,
+    wet_gain: f32,
+
+    fft_size: usize,
+}
+
+impl STFTEngine {
+    fn new(num_channels: usize, fft_size: usize, overlap: usize, zero_padding: usize, plan: usize, band: usize) -> Self {
+        let transform_size = transform_size(fft_size, zero_padding);
+        let mut window = max_size_vec(fft_size);
+        window::fill_window(&mut window, WindowType::Hann, 0.0);
+
+        let mut engine = Self {
+            input_buffer: (0..num_channels).map(|_| max_size_vec(fft_size)).collect(),
+            aux_buffer: None,
+            output_buffer: (0..num_channels).map(|_| max_size_vec(fft_size)).collect(),
+            out_pos: 0,
+            delay: 0,
+
+            window,
+            analysis_gain: 1.0,
+
+            pos: 0,
+            count_to_next_hop: 0,
+
+            overlap,
+            min_overlap: WindowType::Hann.min_overlap(),
+            synthesis_window: max_size_vec(fft_size),
+
+            zero_padding,
+            plan,
+            data: (0..num_channels).map(|_| FFTData::new(transform_size)).collect(),
+            aux_data: None,
+            ifft_in: (0..num_channels)
+                .map(|_| utils::bins_vec(fft_size_to_bins(transform_size), Complex::zero()))
+                .collect(),
+            bypassed_channel: None,
+            band,
+            band_gain: Vec::with_capacity(FFTSize::MAX.num_bins()),
+            band_offset: 0,
+            freqs_sample_rate: 0,
+            dry_gain: 0.0,
+            wet_gain: 1.0,
+
+            fft_size,
+        };
+        engine.calculate_window_gains();
+
+        engine
+    }
+
+    fn enable_aux(&mut self) {
+        let num_channels = self.input_buffer.len();
+        self.aux_buffer = Some((0..num_channels).map(|_| max_size_vec(self.fft_size)).collect());
+        self.aux_data = Some((0..num_channels).map(|_| FFTData::new(self.transform_size())).collect());
+        self.freqs_sample_rate = 0;
+    }
+
+    fn hop_size(&self) -> usize {
+        self.fft_size / self.overlap.max(self.min_overlap)
+    }
+
+    fn transform_size(&self) -> usize {
+        transform_size(self.fft_size, self.zero_padding)
+    }
+
+    /// Resizes everything for `fft_size` and `delay` and clears all buffers.
+    fn configure(
+        &mut self,
+        fft_size: usize,
+        overlap: usize,
+        zero_padding: usize,
+        plan: usize,
+        window_type: WindowType,
+        kaiser_beta: f32,
+    ) {
+        self.fft_size = fft_size;
+        self.overlap = overlap;
+        self.zero_padding = zero_padding;
+        self.plan = plan;
+        let transform_size = self.transform_size();
+
+        self.window.resize(fft_size, 0f32);
+        window::fill_window(&mut self.window, window_type, kaiser_beta);
+        self.min_overlap = window_type.min_overlap();
+
+        for channel in 0..self.input_buffer.len() {
+            self.data[channel].fft_size_change(transform_size);
+
+            self.input_buffer[channel].resize(fft_size, 0f32);
+            self.input_buffer[channel].fill(0f32);
+            self.output_buffer[channel].resize(fft_size + self.delay, 0f32);
+            self.output_buffer[channel].fill(0f32);
+
+            self.ifft_in[channel].resize(fft_size_to_bins(transform_size), Complex::zero());
+            self.ifft_in[channel].fill(Complex::zero());
+        }
+
+        if let (Some(aux_buffer), Some(aux_data)) = (&mut self.aux_buffer, &mut self.aux_data) {
+            for (buffer, data) in aux_buffer.iter_mut().zip(aux_data.iter_mut()) {
+                data.fft_size_change(transform_size);
+                buffer.resize(fft_size, 0f32);
+                buffer.fill(0f32);
+            }
+        }
+
+        self.pos = 0;
+        self.out_pos = 0;
+        self.count_to_next_hop = 0;
+        self.freqs_sample_rate = 0;
+
+        self.calculate_window_gains();
+    }
+
+    /// Fills `band_gain` for this band of `resolution`, a single band doesn't need one and neither
+    /// does the transient band.
+    fn set_band_gain(&mut self, resolution: Resolution, sample_rate: usize, crossovers: (f32, f32)) {
+        self.band_gain.clear();
+        if resolution == Resolution::Single || self.band >= resolution.num_bands() {
+            return;
+        }
+
+        let (band, transform_size) = (self.band, self.transform_size());
+        self.band_gain.extend((0..fft_size_to_bins(transform_size)).map(|bin| {
+            let freq = (bin * sample_rate) as f32 / transform_size as f32;
+            resolution.band_gain(band, freq, crossovers)
+        }));
+    }
+
+    fn set_overlap(&mut self, overlap: usize) {
+        self.overlap = overlap;
+        self.clear_output();
+    }
+
+    /// Like `set_overlap` if the window needs a larger overlap than the current one, or if it no
+    /// longer does.
+    fn set_window(&mut self, window_type: WindowType, kaiser_beta: f32) {
+        let hop_size = self.hop_size();
+        window::fill_window(&mut self.window, window_type, kaiser_beta);
+        self.min_overlap = window_type.min_overlap();
+        if self.hop_size() != hop_size {
+            self.clear_output();
+        } else {
+            self.calculate_window_gains();
+        }
+    }
+
+    // already overlap-added output was scaled for the old hop size
+    fn clear_output(&mut self) {
+        for buffer in self.output_buffer.iter_mut() {
+            buffer.fill(0f32);
+        }
+        self.count_to_next_hop = 0;
+
+        self.calculate_window_gains();
+    }
+
+    fn calculate_window_gains(&mut self) {
+        let window_sum = self.window.iter().sum::<f32>();
+        // * 2.0 (one sided fft correction), / window sum (window correction)
+        self.analysis_gain = 2.0 / window_sum;
+        // the inverse fft isn't normalized and the output gets windowed a second time, so every
+        // output sample is transform_size * analysis_gain * (sum of the overlapping squared windows)
+        let synthesis_gain = 1.0 / (self.transform_size() as f32 * self.analysis_gain);
+        self.synthesis_window.resize(self.fft_size, 0f32);
+        utils::fill_synthesis_window(&self.window, self.hop_size(), synthesis_gain, &mut self.synthesis_window);
+    }
+
+    /// Converts the buffered input and output of the first two channels between left/right and
+    /// mid/side. The aux input isn't converted, its history is in the new format after one window.
+    fn convert_mid_side(&mut self, was_mid_side: bool) {
+        let convert = if was_mid_side { channel_mode::decode_mid_side } else { channel_mode::encode_mid_side };
+        for buffers in [&mut self.input_buffer, &mut self.output_buffer] {
+            let [first, second] = &mut buffers[..] else {
+                continue;
+            };
+            for (a, b) in first.iter_mut().zip(second.iter_mut()) {
+                convert(a, b);
+            }
+        }
+    }
+
+    /// Copies as much of the most recent input of `other` as fits into this (freshly configured)
+    /// engine, so its first frames don't start from silence. Returns the number of copied samples.
+    fn prefill_from(&mut self, other: &STFTEngine) -> usize {
+        let history = self.fft_size.min(other.fft_size);
+        let start = self.fft_size - history;
+        // `other.pos` is where its oldest sample is
+        let other_start = other.pos + other.fft_size - history;
+
+        let copy_history = |buffers: &mut [Vec<f32>], other_buffers: &[Vec<f32>]| {
+            for (buffer, other_buffer) in buffers.iter_mut().zip(other_buffers.iter()) {
+                for i in 0..history {
+                    buffer[start + i] = other_buffer[(other_start + i) % other.fft_size];
+                }
+            }
+        };
+
+        copy_history(&mut self.input_buffer, &other.input_buffer);
+        if let (Some(aux_buffer), Some(other_aux_buffer)) = (&mut self.aux_buffer, &other.aux_buffer) {
+            copy_history(aux_buffer, other_aux_buffer);
+        }
+
+        history
+    }
+
+    /// Block version of `process_sample` for everything in `channels` from `start` on.
+    #[allow(clippy::too_many_arguments)]
+    fn process_block<E: SpectralEffect>(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        aux_channels: Option<&[&mut [f32]]>,
+        start: usize,
+        plans: &mut FFTPlans,
+        fft_effect: &mut E,
+        sample_rate: usize,
+        analyze: bool,
+    ) {
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+        let output_len = self.fft_size + self.delay;
+        let mut offset = start;
+        while offset < num_samples {
+            // copy everything up to the next hop, or up to where the buffers wrap around
+            let len = (num_samples - offset)
+                .min(self.hop_size() - self.count_to_next_hop)
+                .min(self.fft_size - self.pos)
+                .min(output_len - self.out_pos);
+            let block = offset..(offset + len);
+            let buffered = self.pos..(self.pos + len);
+            let output_range = self.out_pos..(self.out_pos + len);
+
+            if let (Some(aux_buffer), Some(aux_channels)) = (&mut self.aux_buffer, aux_channels) {
+                if let Some(last) = aux_channels.len().checked_sub(1) {
+                    for (channel, buffer) in aux_buffer.iter_mut().enumerate() {
+                        buffer[buffered.clone()].copy_from_slice(&aux_channels[channel.min(last)][block.clone()]);
+                    }
+                }
+            }
+
+            for ((channel, input), output) in channels
+                .iter_mut()
+                .zip(self.input_buffer.iter_mut())
+                .zip(self.output_buffer.iter_mut())
+            {
+                let samples = &mut channel[block.clone()];
+                if self.dry_gain != 0.0 || self.wet_gain != 1.0 {
+                    for ((sample, input), output) in samples
+                        .iter_mut()
+                        .zip(input[buffered.clone()].iter_mut())
+                        .zip(output[output_range.clone()].iter())
+                    {
+                        let dry = std::mem::replace(input, *sample);
+                        *sample = dry * self.dry_gain + *output * self.wet_gain;
+                    }
+                } else {
+                    input[buffered.clone()].copy_from_slice(samples);
+                    samples.copy_from_slice(&output[output_range.clone()]);
+                }
+                output[output_range.clone()].fill(0f32);
+            }
+
+            self.pos += len;
+            if self.pos == self.fft_size {
+                self.pos = 0;
+            }
+            self.out_pos += len;
+            if self.out_pos == output_len {
+                self.out_pos = 0;
+            }
+
+            self.count_to_next_hop += len;
+            if self.count_to_next_hop == self.hop_size() {
+                self.count_to_next_hop = 0;
+                self.process_windows(plans, fft_effect, sample_rate, analyze);
+            }
+
+            offset += len;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_sample<E: SpectralEffect>(
+        &mut self,
+        samples: &[f32],
+        aux_samples: Option<&[f32]>,
+        output: &mut [f32],
+        plans: &mut FFTPlans,
+        fft_effect: &mut E,
+        sample_rate: usize,
+        analyze: bool,
+    ) {
+        if let (Some(aux_buffer), Some(aux_samples)) = (&mut self.aux_buffer, aux_samples) {
+            if let Some(last) = aux_samples.len().checked_sub(1) {
+                for (channel, buffer) in aux_buffer.iter_mut().enumerate() {
+                    buffer[self.pos] = aux_samples[channel.min(last)];
+                }
+            }
+        }
+
+        // copy each sample into the channel buffers
+        for (channel, sample) in samples.iter().enumerate() {
+            let dry = std::mem::replace(&mut self.input_buffer[channel][self.pos], *sample);
+            let wet = std::mem::replace(&mut self.output_buffer[channel][self.out_pos], 0f32);
+            output[channel] = dry * self.dry_gain + wet * self.wet_gain;
+        }
+
+        //increment the buffer position. All input buffers use the same position
+        self.pos += 1;
+        if self.pos == self.fft_size {
+            self.pos = 0;
+        }
+        self.out_pos += 1;
+        if self.out_pos == self.output_buffer[0].len() {
+            self.out_pos = 0;
+        }
+
+        self.count_to_next_hop += 1;
+        if self.count_to_next_hop == self.hop_size() {
+            self.count_to_next_hop = 0;
+            self.process_windows(plans, fft_effect, sample_rate, analyze);
+        }
+    }
+
+    fn process_windows<E: SpectralEffect>(
+        &mut self,
+        plans: &mut FFTPlans,
+        fft_effect: &mut E,
+        sample_rate: usize,
+        analyze: bool,
+    ) {
+        for channel in 0..self.input_buffer.len() {
+            forward_fft(
+                plans,
+                self.plan,
+                &self.input_buffer[channel],
+                &mut self.data[channel],
+                &self.window,
+                self.analysis_gain,
+                self.pos,
+            );
+        }
+        if let (Some(aux_buffer), Some(aux_data)) = (&self.aux_buffer, &mut self.aux_data) {
+            for (buffer, data) in aux_buffer.iter().zip(aux_data.iter_mut()) {
+                forward_fft(
+                    plans,
+                    self.plan,
+                    buffer,
+                    data,
+                    &self.window,
+                    self.analysis_gain,
+                    self.pos,
+                );
+            }
+        }
+
+        // calculate the values the effect asks for (magnitude, phase, magnitude in dB, phase advance)
+        fft_effect.set_band(self.band_offset + self.band);
+        self.calculate_fft_values(sample_rate, fft_effect.spectrum_values());
+        // MAIN FFT-BASED PROCESSING
+        fft_effect.process_spectrum(&self.data, self.aux_data.as_deref(), &mut self.ifft_in);
+        if let Some(channel) = self.bypassed_channel {
+            self.ifft_in[channel].copy_from_slice(&self.data[channel].fft_out);
+        }
+
+        if analyze {
+            self.calculate_analyzer_db();
+            fft_effect.analyze(&self.data, self.aux_data.as_deref(), self.transform_size());
+        }
+
+        // every band only resynthesizes its own frequency range
+        if !self.band_gain.is_empty() {
+            for ifft_in in self.ifft_in.iter_mut() {
+                for (bin, gain) in ifft_in.iter_mut().zip(self.band_gain.iter()) {
+                    *bin *= gain;
+                }
+            }
+        }
+
+        // inverse FFT from processed bins
+        for channel in 0..self.input_buffer.len() {
+            plans.inverse(self.plan, &mut self.ifft_in[channel], &mut self.data[channel].ifft_out);
+            // only the part of the output the window covers is used, the padding is dropped
+            utils::multiply_vectors_in_place(&mut self.data[channel].ifft_out[..self.fft_size], &self.synthesis_window);
+
+            // the frame starts `delay` samples after the next output sample
+            let output = &mut self.output_buffer[channel];
+            let start = (self.out_pos + self.delay) % output.len();
+            let split = (output.len() - start).min(self.fft_size);
+            let ifft_out = &self.data[channel].ifft_out[..self.fft_size];
+            for (out, sample) in output[start..(start + split)].iter_mut().zip(ifft_out[..split].iter()) {
+                *out += sample;
+            }
+            for (out, sample) in output.iter_mut().zip(ifft_out[split..].iter()) {
+                *out += sample;
+            }
+        }
+    }
+
+    fn calculate_fft_values(&mut self, sample_rate: usize, values: SpectrumValues) {
+        let (transform_size, hop_size) = (self.transform_size(), self.hop_size());
+        let fill_freqs = self.freqs_sample_rate != sample_rate;
+        self.freqs_sample_rate = sample_rate;
+
+        let aux_data = self.aux_data.as_deref_mut().unwrap_or_default();
+        for data in self.data.iter_mut().chain(aux_data.iter_mut()) {
+            if fill_freqs {
+                data.fill_freqs(sample_rate, transform_size);
+            }
+            calculate_fft_values(data, values, sample_rate, transform_size, hop_size);
+        }
+    }
+
+    fn calculate_analyzer_db(&mut self) {
+        let num_bins = fft_size_to_bins(self.transform_size());
+        let aux_data = self.aux_data.as_deref_mut().unwrap_or_default();
+        // `fft_out` still holds the input, `process_spectrum` only writes to `ifft_in`
+        for data in self.data.iter_mut().chain(aux_data.iter_mut()) {
+            for i in 1..num_bins - 1 {
+                data.analyzer_input_db[i] = utils::gain_to_db(data.fft_out[i].norm());
+            }
+        }
+        for (data, ifft_in) in self.data.iter_mut().zip(self.ifft_in.iter()) {
+            for (i, bin) in ifft_in.iter().enumerate().take(num_bins - 1).skip(1) {
+                data.spectrum_mag[i] = bin.norm();
+                data.spectrum_db[i] = utils::gain_to_db(data.spectrum_mag[i]);
+            }
+        }
+    }
+}
+
+/// Copies the circular `buffer` into `data.fft_in` starting at `pos` so that it's continuous,
+/// windows it, zero pads it to the transform size, runs the forward FFT and scales the result by
+/// `gain`.
+fn forward_fft(
+    plans: &mut FFTPlans,
+    plan: usize,
+    buffer: &[f32],
+    data: &mut FFTData,
+    window: &[f32],
+    gain: f32,
+    pos: usize,
+) {
+    let fft_size = buffer.len();
+
+    // properly copy the input buffer and make it continous
+    let len = fft_size - pos;
+    data.fft_in[..len].copy_from_slice(&buffer[pos..]);
+    data.fft_in[len..fft_size].copy_from_slice(&buffer[..pos]);
+    // the forward fft uses its input as scratch space, so the padding has to be cleared every time
+    data.fft_in[fft_size..].fill(0f32);
+
+    // multiply the input vector by a window to prevent spectral leakage
+    utils::multiply_vectors_in_place(&mut data.fft_in, window);
+
+    // do forward FFT
+    plans.forward(plan, &mut data.fft_in, &mut data.fft_out);
+    // window and one-sided fft correction
+    for i in data.fft_out.iter_mut() {
+        *i *= gain;
+    }
+}
+
+fn transform_size(fft_size: usize, zero_padding: usize) -> usize {
+    (fft_size * zero_padding).min(FFTSize::MAX as usize)
+}
+
+/// Zeroed vec of `len` samples with room for the largest `FFTSize`.
+fn max_size_vec(len: usize) -> Vec<f32> {
+    utils::vec_with_capacity(len, FFTSize::MAX as usize, 0f32)
+}
+
+fn calculate_fft_values(
+    data: &mut FFTData,
+    values: SpectrumValues,
+    sample_rate: usize,
+    fft_size: usize,
+    hop_size: usize,
+) {
+    if values == SpectrumValues::NONE {
+        return;
+    }
+
+    for i in 1..(data.fft_out.len() - 1) {
+        if values.magnitude || values.db {
+            data.spectrum_mag[i] = data.fft_out[i].norm();
+        }
+        if values.db {
+            data.spectrum_db[i] = utils::gain_to_db(data.spectrum_mag[i]);
+        }
+        if values.phase || values.phase_tracking {
+            data.spectrum_phase[i] = data.fft_out[i].arg();
+        }
+        if values.phase_tracking {
+            data.track_phase(i, fft_size, hop_size, sample_rate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smoothing::HopSmoother;
+
+    struct Passthrough;
+
+    impl SpectralEffect for Passthrough {
+        fn process_spectrum(&mut self, input: &[FFTData], _aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            for (data, out) in input.iter().zip(output.iter_mut()) {
+                out.copy_from_slice(&data.fft_out);
+            }
+        }
+    }
+
+    /// Outputs the aux input instead of the main input.
+    struct AuxPassthrough;
+
+    impl SpectralEffect for AuxPassthrough {
+        fn process_spectrum(&mut self, _input: &[FFTData], aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            for (data, out) in aux.unwrap().iter().zip(output.iter_mut()) {
+                out.copy_from_slice(&data.fft_out);
+            }
+        }
+    }
+
+    /// Outputs silence.
+    struct Mute;
+
+    impl SpectralEffect for Mute {
+        fn process_spectrum(&mut self, _input: &[FFTData], _aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            for out in output.iter_mut() {
+                out.fill(Complex::zero());
+            }
+        }
+    }
+
+    /// Mixes half of the main input with a quarter of the aux input.
+    struct AuxMix;
+
+    impl SpectralEffect for AuxMix {
+        fn process_spectrum(&mut self, input: &[FFTData], aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            for ((data, aux_data), out) in input.iter().zip(aux.unwrap().iter()).zip(output.iter_mut()) {
+                for ((out, bin), aux_bin) in out.iter_mut().zip(data.fft_out.iter()).zip(aux_data.fft_out.iter()) {
+                    *out = bin * 0.5 + aux_bin * 0.25;
+                }
+            }
+        }
+    }
+
+    // muting every other bin aliases each frame by half a window, which turns into a pre-echo
+    struct OddBinsMuted;
+
+    impl SpectralEffect for OddBinsMuted {
+        fn process_spectrum(&mut self, input: &[FFTData], _aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            for (data, output) in input.iter().zip(output.iter_mut()) {
+                for (bin, (output, input)) in output.iter_mut().zip(data.fft_out.iter()).enumerate() {
+                    *output = if bin % 2 == 1 { Complex::zero() } else { *input };
+                }
+            }
+        }
+    }
+
+    // a gain mask straight on the complex bins, without any polar values
+    struct ComplexHalfGain;
+
+    impl SpectralEffect for ComplexHalfGain {
+        fn spectrum_values(&self) -> SpectrumValues {
+            SpectrumValues::NONE
+        }
+
+        fn process_spectrum(&mut self, input: &[FFTData], _aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            for (data, output) in input.iter().zip(output.iter_mut()) {
+                for (output, input) in output.iter_mut().zip(data.fft_out.iter()) {
+                    *output = input * 0.5;
+                }
+            }
+        }
+    }
+
+    // remembers what it was told about the sample rate and where the main band's spectral peak is
+    #[derive(Default)]
+    struct SampleRateProbe {
+        sample_rate: usize,
+        hop_size: usize,
+        band: usize,
+        peak_freq: f32,
+    }
+
+    impl SpectralEffect for SampleRateProbe {
+        fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+            self.sample_rate = sample_rate;
+            self.hop_size = hop_size;
+        }
+
+        fn set_band(&mut self, band: usize) {
+            self.band = band;
+        }
+
+        fn process_spectrum(&mut self, input: &[FFTData], _aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            if self.band == 0 {
+                let data = &input[0];
+                let peak = (0..data.spectrum_mag.len()).max_by(|&a, &b| data.spectrum_mag[a].total_cmp(&data.spectrum_mag[b])).unwrap();
+                self.peak_freq = data.spectrum_inst_freq[peak];
+            }
+            for (data, out) in input.iter().zip(output.iter_mut()) {
+                out.copy_from_slice(&data.fft_out);
+            }
+        }
+    }
+
+    // a gain that ramps in over a few hops like the plugins' parameters do
+    struct SmoothedGain {
+        gain: HopSmoother,
+        band: usize,
+    }
+
+    impl SpectralEffect for SmoothedGain {
+        fn set_sample_rate(&mut self, sample_rate: usize, hop_size: usize) {
+            self.gain.set_hop_rate(sample_rate, hop_size);
+        }
+
+        fn set_band(&mut self, band: usize) {
+            self.band = band;
+        }
+
+        fn process_spectrum(&mut self, input: &[FFTData], _aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            let gain = if self.band == 0 { self.gain.next_hop() } else { self.gain.value() };
+            for (data, out) in input.iter().zip(output.iter_mut()) {
+                for (out, bin) in out.iter_mut().zip(data.fft_out.iter()) {
+                    *out = bin * gain;
+                }
+            }
+        }
+    }
+
+    // checks that every spectrum has the size `set_bands` gave its band
+    #[derive(Default)]
+    struct BandProbe {
+        bands: Vec<BandSize>,
+        band: usize,
+        seen: [bool; MAX_EFFECT_BANDS],
+        mismatches: usize,
+        crossfades: usize,
+    }
+
+    impl SpectralEffect for BandProbe {
+        fn set_bands(&mut self, bands: &[BandSize]) {
+            self.bands = bands.to_vec();
+        }
+
+        fn set_band(&mut self, band: usize) {
+            self.band = band;
+        }
+
+        fn crossfade_started(&mut self) {
+            self.crossfades += 1;
+        }
+
+        fn process_spectrum(&mut self, input: &[FFTData], _aux: Option<&[FFTData]>, output: &mut [Vec<Complex<f32>>]) {
+            self.seen[self.band] = true;
+            if input[0].fft_out.len() != fft_size_to_bins(self.bands[self.band].transform_size) {
+                self.mismatches += 1;
+            }
+            for (data, out) in input.iter().zip(output.iter_mut()) {
+                out.copy_from_slice(&data.fft_out);
+            }
+        }
+    }
+
+    fn process_stereo<E: SpectralEffect>(processor: &mut FFTProcessor<E>, mut samples: [f32; 2]) -> [f32; 2] {
+        processor.process_sample(&mut samples);
+        samples
+    }
+
+    const WINDOWS: [WindowType; 7] = [
+        WindowType::Hann,
+        WindowType::Hamming,
+        WindowType::Blackman,
+        WindowType::BlackmanHarris,
+        WindowType::Nuttall,
+        WindowType::Kaiser,
+        WindowType::FlatTop,
+    ];
+
+    #[test]
+    fn passthrough_is_unity_gain_test() {
+        for window_type in WINDOWS {
+            for overlap in [2, 4, 8, 16] {
+                let fft_size = 1024;
+                let mut processor = FFTProcessor::new(44100, 2, fft_size, overlap, Passthrough);
+                processor.set_window(window_type, 8.0);
+                // a constant offset shows any amplitude modulation from the overlapping windows
+                let input = |i: usize| 0.25 + (i as f32 * 0.05).sin() * 0.5;
+
+                // the error sample by sample, the output has to follow the input and not just match its level
+                let (mut min_error, mut max_error) = (0f32, 0f32);
+                for i in 0..(fft_size * 8) {
+                    let out = process_stereo(&mut processor, [input(i), -input(i)]);
+                    if i >= fft_size * 2 {
+                        for error in [out[0] - input(i - fft_size), out[1] + input(i - fft_size)] {
+                            min_error = min_error.min(error);
+                            max_error = max_error.max(error);
+                        }
+                    }
+                }
+                assert!(
+                    max_error - min_error < 1e-3,
+                    "{window_type:?} at overlap {overlap}: error between {min_error} and {max_error}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn window_normalization_test() {
+        for window_type in WINDOWS {
+            for overlap in [2, 4, 8, 16] {
+                let fft_size = 1024;
+                let mut processor = FFTProcessor::new(44100, 2, fft_size, overlap, Passthrough);
+                processor.set_window(window_type, 8.0);
+                // a 0.5 amplitude sine right on bin 32
+                let input = |i: usize| (2.0 * std::f32::consts::PI * 32.0 * i as f32 / fft_size as f32).sin() * 0.5;
+
+                let (mut min_error, mut max_error) = (0f32, 0f32);
+                for i in 0..(fft_size * 8) {
+                    let out = process_stereo(&mut processor, [input(i), input(i)]);
+                    if i >= fft_size * 2 {
+                        min_error = min_error.min(out[0] - input(i - fft_size));
+                        max_error = max_error.max(out[0] - input(i - fft_size));
+                    }
+                }
+                assert!(
+                    max_error - min_error < 1e-3,
+                    "{window_type:?} at overlap {overlap}: error between {min_error} and {max_error}"
+                );
+
+                processor.process_windows();
+                let peak = processor.engines[processor.active].main().data[0].fft_out[32].norm();
+                assert!((peak - 0.5).abs() < 0.01, "{window_type:?}: peak {peak}");
+            }
+        }
+    }
+
+    #[test]
+    fn window_raises_overlap_test() {
+        let fft_size = 1024;
+        let mut processor = FFTProcessor::new(44100, 2, fft_size, 2, Passthrough);
+        let input = |i: usize| 0.25 + (i as f32 * 0.05).sin() * 0.5;
+
+        for window_type in WINDOWS {
+            processor.set_window(window_type, 8.0);
+            assert_eq!(processor.overlap(), 2);
+            assert_eq!(processor.hop_size(), fft_size / window_type.min_overlap());
+
+            // switching windows while playing is back to unity gain once the old frames are out
+            let mut max_error = 0f32;
+            for i in 0..(fft_size * 4) {
+                let out = process_stereo(&mut processor, [input(i), input(i)]);
+                if i >= fft_size * 2 {
+                    max_error = max_error.max((out[0] - input(i - fft_size)).abs());
+                }
+            }
+            assert!(max_error < 1e-3, "{window_type:?}: max error {max_error}");
+        }
+
+        processor.set_window(WindowType::Hann, 0.0);
+        assert_eq!(processor.hop_size(), fft_size / 2);
+    }
+
+    #[test]
+    fn fft_size_change_keeps_allocations_test() {
+        let mut processor = FFTProcessor::new(44100, 2, 1024, 4, Passthrough);
+        let window_ptr = processor.engines[processor.active].main().window.as_ptr();
+        let fft_in_ptr = processor.engines[processor.active].main().data[0].fft_in.as_ptr();
+        let ifft_in_ptr = processor.engines[processor.active].main().ifft_in[0].as_ptr();
+
+        for size in [128, 32768, 2048] {
+            processor.change_fft_size(size);
+            assert_eq!(window_ptr, processor.engines[processor.active].main().window.as_ptr());
+            assert_eq!(fft_in_ptr, processor.engines[processor.active].main().data[0].fft_in.as_ptr());
+            assert_eq!(ifft_in_ptr, processor.engines[processor.active].main().ifft_in[0].as_ptr());
+        }
+
+        // still unity gain at the new size
+        let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+        let mut max_error = 0f32;
+        for i in 0..(2048 * 8) {
+            let out = process_stereo(&mut processor, [input(i), input(i)]);
+            if i >= 2048 * 2 {
+                max_error = max_error.max((out[0] - input(i - 2048)).abs());
+            }
+        }
+        assert!(max_error < 0.01, "max error {max_error}");
+    }
+
+    #[test]
+    fn crossfade_fft_size_change_test() {
+        for (from, to) in [(1024, 2048), (2048, 512)] {
+            let mut processor = FFTProcessor::new(44100, 2, from, 4, Passthrough);
+            let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+
+            let mut max_step = 0f32;
+            let mut previous = 0f32;
+            let mut i = 0;
+            while i < from * 4 {
+                previous = process_stereo(&mut processor, [input(i), input(i)])[0];
+                i += 1;
+            }
+
+            processor.crossfade_to(to, 4, 1);
+            assert_eq!(processor.latency_samples(), to);
+            while processor.is_crossfading() {
+                let out = process_stereo(&mut processor, [input(i), input(i)])[0];
+                max_step = max_step.max((out - previous).abs());
+                previous = out;
+                i += 1;
+            }
+            // a 0.5 amplitude sine at 0.05 rad per sample never moves more than 0.025 per sample,
+            // the equal-power fade of two correlated signals can add up to sqrt(2) on top of that
+            assert!(max_step < 0.04, "{from} -> {to}: max step {max_step}");
+
+            // and the new size is unity gain with its own latency
+            let mut max_error = 0f32;
+            for _ in 0..(to * 4) {
+                let out = process_stereo(&mut processor, [input(i), input(i)])[0];
+                max_error = max_error.max((out - input(i - to)).abs());
+                i += 1;
+            }
+            assert!(max_error < 0.01, "{from} -> {to}: max error {max_error}");
+        }
+    }
+
+    #[test]
+    fn crossfade_queues_changes_test() {
+        let mut processor = FFTProcessor::new(44100, 2, 1024, 4, Passthrough);
+        processor.crossfade_to(2048, 4, 1);
+        processor.crossfade_to(512, 8, 1);
+        assert_eq!(processor.latency_samples(), 512);
+        assert_eq!(processor.fft_size(), 2048);
+
+        let mut samples = 0;
+        while processor.is_crossfading() {
+            process_stereo(&mut processor, [0.0, 0.0]);
+            samples += 1;
+        }
+        assert_eq!(processor.fft_size(), 512);
+        assert_eq!(processor.overlap(), 8);
+        // 1024 -> 2048 starts with 1024 samples of history, 2048 -> 512 with a full window of it
+        assert_eq!(samples, (2 * 2048 - 1024 + 2048) + (2 * 512 - 512 + 512));
+    }
+
+    #[test]
+    fn crossfade_keeps_engine_bands_apart_test() {
+        let mut processor = FFTProcessor::new(44100, 2, 1024, 4, BandProbe::default());
+        for _ in 0..4096 {
+            process_stereo(&mut processor, [0.1, 0.1]);
+        }
+
+        processor.crossfade_to_resolution(2048, 4, 1, Resolution::Dual);
+        assert_eq!(processor.fft_effect.crossfades, 1);
+        assert_eq!(processor.fft_effect.bands.len(), MAX_BANDS + 1);
+        processor.fft_effect.seen = Default::default();
+        while processor.is_crossfading() {
+            process_stereo(&mut processor, [0.1, 0.1]);
+        }
+        // the incoming engine's two bands and the outgoing engine's single one
+        let seen = processor.fft_effect.seen;
+        assert!(seen[0] && seen[1] && seen[MAX_BANDS], "{seen:?}");
+        assert_eq!(processor.fft_effect.mismatches, 0);
+
+        assert_eq!(processor.fft_effect.bands.len(), 2);
+        processor.fft_effect.seen = Default::default();
+        for _ in 0..8192 {
+            process_stereo(&mut processor, [0.1, 0.1]);
+        }
+        assert!(!processor.fft_effect.seen[MAX_BANDS..].contains(&true));
+        assert_eq!(processor.fft_effect.mismatches, 0);
+    }
+
+    #[test]
+    fn multichannel_passthrough_test() {
+        for num_channels in [1, 6, 8] {
+            let fft_size = 1024;
+            let mut processor = FFTProcessor::new(44100, num_channels, fft_size, 4, Passthrough);
+            // every channel gets its own frequency
+            let input = |i: usize, channel: usize| (i as f32 * 0.01 * (channel + 1) as f32).sin() * 0.5;
+
+            let mut max_error = 0f32;
+            let mut samples = [0f32; MAX_CHANNELS];
+            for i in 0..(fft_size * 8) {
+                for (channel, sample) in samples[..num_channels].iter_mut().enumerate() {
+                    *sample = input(i, channel);
+                }
+                processor.process_sample(&mut samples[..num_channels]);
+                if i >= fft_size * 2 {
+                    for (channel, sample) in samples[..num_channels].iter().enumerate() {
+                        max_error = max_error.max((sample - input(i - fft_size, channel)).abs());
+                    }
+                }
+            }
+            assert!(max_error < 0.01, "{num_channels} channels: max error {max_error}");
+        }
+    }
+
+    #[test]
+    fn mono_aux_feeds_every_channel_test() {
+        let fft_size = 1024;
+        let mut processor = FFTProcessor::new_with_aux(44100, 2, fft_size, 4, AuxPassthrough);
+        let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+
+        let mut max_error = 0f32;
+        for i in 0..(fft_size * 8) {
+            let mut samples = [0.0, 0.0];
+            processor.process_sample_with_aux(&mut samples, &[input(i)]);
+            if i >= fft_size * 2 {
+                max_error = max_error.max((samples[0] - input(i - fft_size)).abs());
+                max_error = max_error.max((samples[1] - input(i - fft_size)).abs());
+            }
+        }
+        assert!(max_error < 0.01, "max error {max_error}");
+    }
+
+    #[test]
+    fn mid_side_modes_test() {
+        let fft_size = 1024;
+        let left = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+        let right = |i: usize| (i as f32 * 0.013).sin() * 0.25;
+        let mid = |i: usize| (left(i) + right(i)) * 0.5;
+        let side = |i: usize| (left(i) - right(i)) * 0.5;
+
+        // muting everything the effect sees leaves only the bypassed channel
+        for channel_mode in [ChannelMode::Mid, ChannelMode::Side] {
+            let expected = |i: usize| match channel_mode {
+                ChannelMode::Mid => [side(i), -side(i)],
+                _ => [mid(i), mid(i)],
+            };
+            let mut processor = FFTProcessor::new(44100, 2, fft_size, 4, Mute);
+            processor.set_channel_mode(channel_mode);
+
+            let mut max_error = 0f32;
+            for i in 0..(fft_size * 8) {
+                let out = process_stereo(&mut processor, [left(i), right(i)]);
+                if i >= fft_size * 2 {
+                    let expected = expected(i - fft_size);
+                    max_error = max_error.max((out[0] - expected[0]).abs());
+                    max_error = max_error.max((out[1] - expected[1]).abs());
+                }
+            }
+            assert!(max_error < 0.01, "{channel_mode:?}: max error {max_error}");
+        }
+    }
+
+    #[test]
+    fn mid_side_switch_is_seamless_test() {
+        let fft_size = 1024;
+        let mut processor = FFTProcessor::new(44100, 2, fft_size, 4, Passthrough);
+        let left = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+        let right = |i: usize| (i as f32 * 0.013).sin() * 0.25;
+
+        let mut max_error = 0f32;
+        for i in 0..(fft_size * 8) {
+            if i == fft_size * 3 + 100 {
+                processor.set_channel_mode(ChannelMode::MidSide);
+            }
+            if i == fft_size * 5 + 300 {
+                processor.set_channel_mode(ChannelMode::Stereo);
+            }
+            let out = process_stereo(&mut processor, [left(i), right(i)]);
+            if i >= fft_size * 2 {
+                max_error = max_error.max((out[0] - left(i - fft_size)).abs());
+                max_error = max_error.max((out[1] - right(i - fft_size)).abs());
+            }
+        }
+        assert!(max_error < 0.01, "max error {max_error}");
+    }
+
+    #[test]
+    fn process_block_matches_process_sample_test() {
+        let mut per_sample = FFTProcessor::new_with_aux(44100, 2, 1024, 4, AuxMix);
+        let mut per_block = FFTProcessor::new_with_aux(44100, 2, 1024, 4, AuxMix);
+        for processor in [&mut per_sample, &mut per_block] {
+            processor.set_channel_mode(ChannelMode::MidSide);
+        }
+        let input = |i: usize, channel: usize| (i as f32 * 0.01 * (channel + 1) as f32).sin() * 0.5;
+
+        let mut i = 0;
+        let mut max_error = 0f32;
+        for (block, block_size) in [1, 37, 512, 999, 64, 1500].into_iter().cycle().take(80).enumerate() {
+            // crossfades switch the block version to going sample by sample for a while
+            if block == 10 {
+                for processor in [&mut per_sample, &mut per_block] {
+                    processor.crossfade_to_resolution(2048, 8, 1, Resolution::Triple);
+                }
+            }
+
+            let mut left: Vec<f32> = (i..(i + block_size)).map(|i| input(i, 0)).collect();
+            let mut right: Vec<f32> = (i..(i + block_size)).map(|i| input(i, 1)).collect();
+            let mut aux_left: Vec<f32> = (i..(i + block_size)).map(|i| input(i, 2)).collect();
+            let mut aux_right: Vec<f32> = (i..(i + block_size)).map(|i| input(i, 3)).collect();
+
+            let mut expected = Vec::new();
+            for j in 0..block_size {
+                let mut samples = [left[j], right[j]];
+                per_sample.process_sample_with_aux(&mut samples, &[aux_left[j], aux_right[j]]);
+                expected.push(samples);
+            }
+
+            per_block.process_block_with_aux(&mut [&mut left, &mut right], &mut [&mut aux_left, &mut aux_right]);
+            for (j, expected) in expected.iter().enumerate() {
+                max_error = max_error.max((left[j] - expected[0]).abs());
+                max_error = max_error.max((right[j] - expected[1]).abs());
+            }
+            i += block_size;
+        }
+        assert_eq!(per_block.fft_size(), 2048);
+        assert_eq!(per_block.resolution(), Resolution::Triple);
+        assert!(!per_block.is_crossfading());
+        assert!(max_error < 1e-6, "max error {max_error}");
+    }
+
+    #[test]
+    fn dry_signal_is_latency_compensated_test() {
+        let fft_size = 1024;
+        let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+        for mix in [0.0, 0.25, 0.5] {
+            // with a muted effect all that's left is the dry signal
+            let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, Mute);
+            processor.set_mix(mix);
+
+            let mut max_error = 0f32;
+            for block in 0..16 {
+                let start = block * 700;
+                let mut samples: Vec<f32> = (start..(start + 700)).map(input).collect();
+                processor.process_block(&mut [&mut samples]);
+                for (i, sample) in (start..).zip(samples) {
+                    let expected = if i >= fft_size { input(i - fft_size) * (1.0 - mix) } else { 0.0 };
+                    max_error = max_error.max((sample - expected).abs());
+                }
+            }
+            assert!(max_error < 1e-6, "mix {mix}: max error {max_error}");
+        }
+    }
+
+    #[test]
+    fn instantaneous_frequency_test() {
+        let fft_size = 1024;
+        let sample_rate = 44100;
+        // falls between the centers of bins 23 and 24
+        let frequency = 1000.0;
+        let bin = utils::freq_to_bin(frequency, fft_size, sample_rate as f32);
+        let mut processor = FFTProcessor::new(sample_rate, 1, fft_size, 4, Passthrough);
+
+        for i in 0..(fft_size * 4) {
+            let mut samples = [(std::f32::consts::TAU * frequency * i as f32 / sample_rate as f32).sin()];
+            processor.process_sample(&mut samples);
+        }
+
+        let data = &processor.engines[processor.active].main().data[0];
+        for bin in [bin, bin + 1] {
+            let error = (data.spectrum_inst_freq[bin] - frequency).abs();
+            assert!(error < 1.0, "bin {bin}: {} Hz", data.spectrum_inst_freq[bin]);
+        }
+        let hop_size = processor.hop_size() as f32;
+        let expected_advance = std::f32::consts::TAU * frequency * hop_size / sample_rate as f32;
+        assert!((data.spectrum_phase_advance[bin] - expected_advance).abs() < 0.01);
+    }
+
+    #[test]
+    fn zero_padding_test() {
+        let fft_size = 1024;
+        // a 0.5 amplitude sine right on bin 32 of the unpadded transform
+        let input = |i: usize| (2.0 * std::f32::consts::PI * 32.0 * i as f32 / fft_size as f32).sin() * 0.5;
+        for zero_padding in [2, 4] {
+            let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, Passthrough);
+            processor.change_zero_padding(zero_padding);
+            assert_eq!(processor.transform_size(), fft_size * zero_padding);
+
+            let mut max_error = 0f32;
+            for i in 0..(fft_size * 8) {
+                let mut samples = [input(i)];
+                processor.process_sample(&mut samples);
+                if i >= fft_size * 2 {
+                    max_error = max_error.max((samples[0] - input(i - fft_size)).abs());
+                }
+            }
+            assert!(max_error < 0.01, "{zero_padding}x: max error {max_error}");
+
+            // the peak moves up by the padding factor but keeps its level
+            let data = &processor.engines[processor.active].main().data[0];
+            let peak = data.fft_out[32 * zero_padding].norm();
+            assert!((peak - 0.5).abs() < 0.01, "{zero_padding}x: peak {peak}");
+        }
+
+        // the transform can't get longer than the largest fft size
+        let mut processor = FFTProcessor::new(44100, 1, 16384, 4, Passthrough);
+        processor.change_zero_padding(4);
+        assert_eq!(processor.transform_size(), FFTSize::MAX as usize);
+    }
+
+    #[test]
+    fn multi_resolution_passthrough_test() {
+        let fft_size = 1024;
+        // one sine in each band and one right at the low crossover
+        let input = |i: usize| {
+            [100.0, 300.0, 1000.0, 9000.0]
+                .iter()
+                .map(|freq| (std::f32::consts::TAU * freq * i as f32 / 44100.0).sin() * 0.2)
+                .sum::<f32>()
+        };
+        for resolution in [Resolution::Dual, Resolution::Triple] {
+            let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, Passthrough);
+            processor.change_resolution(resolution);
+            let latency = processor.latency_samples();
+            assert_eq!(latency, fft_size * 4);
+
+            let mut max_error = 0f32;
+            for i in 0..(latency * 6) {
+                let mut samples = [input(i)];
+                processor.process_sample(&mut samples);
+                if i >= latency * 2 {
+                    max_error = max_error.max((samples[0] - input(i - latency)).abs());
+                }
+            }
+            assert!(max_error < 0.01, "{resolution:?}: max error {max_error}");
+        }
+    }
+
+    #[test]
+    fn crossfade_to_multi_resolution_test() {
+        let fft_size = 1024;
+        let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, Passthrough);
+        processor.set_mix(0.5);
+        let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+
+        let mut previous = 0f32;
+        let mut i = 0;
+        while i < fft_size * 4 {
+            let mut samples = [input(i)];
+            processor.process_sample(&mut samples);
+            previous = samples[0];
+            i += 1;
+        }
+
+        processor.crossfade_to_resolution(fft_size, 4, 1, Resolution::Triple);
+        assert_eq!(processor.latency_samples(), fft_size * 4);
+        let mut max_step = 0f32;
+        while processor.is_crossfading() {
+            let mut samples = [input(i)];
+            processor.process_sample(&mut samples);
+            max_step = max_step.max((samples[0] - previous).abs());
+            previous = samples[0];
+            i += 1;
+        }
+        assert!(max_step < 0.04, "max step {max_step}");
+
+        // the dry half is delayed along with the bands
+        let mut max_error = 0f32;
+        for _ in 0..(fft_size * 8) {
+            let mut samples = [input(i)];
+            processor.process_sample(&mut samples);
+            max_error = max_error.max((samples[0] - input(i - fft_size * 4)).abs());
+            i += 1;
+        }
+        assert!(max_error < 0.01, "max error {max_error}");
+
+        // size changes keep the bands
+        processor.crossfade_to(fft_size * 2, 4, 1);
+        assert_eq!(processor.resolution(), Resolution::Triple);
+        assert_eq!(processor.latency_samples(), fft_size * 8);
+    }
+
+    #[test]
+    fn transient_switching_reduces_pre_echo_test() {
+        let (fft_size, short_size, onset) = (2048, 256, 20000);
+        // a burst out of silence
+        let input = |i: usize| if (onset..onset + 400).contains(&i) { (i as f32 * 0.3).sin() * 0.5 } else { 0.0 };
+
+        let mut pre_echo = [0f32; 2];
+        for (transients, pre_echo) in [false, true].into_iter().zip(pre_echo.iter_mut()) {
+            let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, OddBinsMuted);
+            processor.set_transient_switching(transients);
+            for i in 0..(onset + fft_size * 2) {
+                let mut samples = [input(i)];
+                processor.process_sample(&mut samples);
+                // the output for everything from a window to a short window before the onset
+                if (onset..onset + fft_size - short_size).contains(&i) {
+                    *pre_echo += samples[0] * samples[0];
+                }
+            }
+            assert_eq!(processor.transient_switching(), transients);
+            assert_eq!(processor.latency_samples(), fft_size);
+        }
+        assert!(pre_echo[0] > 0.1, "no pre-echo to begin with: {}", pre_echo[0]);
+        assert!(pre_echo[1] < pre_echo[0] * 0.01, "pre-echo {} vs {}", pre_echo[1], pre_echo[0]);
+    }
+
+    #[test]
+    fn transient_switching_block_matches_process_sample_test() {
+        let mut per_sample = FFTProcessor::new(44100, 2, 1024, 4, OddBinsMuted);
+        let mut per_block = FFTProcessor::new(44100, 2, 1024, 4, OddBinsMuted);
+        for processor in [&mut per_sample, &mut per_block] {
+            processor.change_resolution(Resolution::Dual);
+            processor.set_transient_switching(true);
+        }
+        // bursts that keep the switch busy, some of them while it's still holding
+        let input = |i: usize, channel: usize| match i % 7000 {
+            0..=299 | 3000..=3099 => (i as f32 * 0.02 * (channel + 1) as f32).sin() * 0.5,
+            _ => 0.0,
+        };
+
+        let mut i = 0;
+        let mut max_error = 0f32;
+        for block_size in [1, 37, 512, 999, 64, 1500].into_iter().cycle().take(60) {
+            let mut left: Vec<f32> = (i..(i + block_size)).map(|i| input(i, 0)).collect();
+            let mut right: Vec<f32> = (i..(i + block_size)).map(|i| input(i, 1)).collect();
+
+            let mut expected = Vec::new();
+            for j in 0..block_size {
+                expected.push(process_stereo(&mut per_sample, [left[j], right[j]]));
+            }
+
+            per_block.process_block(&mut [&mut left, &mut right]);
+            for (j, expected) in expected.iter().enumerate() {
+                max_error = max_error.max((left[j] - expected[0]).abs());
+                max_error = max_error.max((right[j] - expected[1]).abs());
+            }
+            i += block_size;
+        }
+        assert!(per_block.transient_switching());
+        assert!(max_error < 1e-6, "max error {max_error}");
+    }
+
+    #[test]
+    fn complex_effect_skips_polar_values_test() {
+        let fft_size = 1024;
+        let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+        let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, ComplexHalfGain);
+
+        let mut max_error = 0f32;
+        for i in 0..(fft_size * 6) {
+            let mut samples = [input(i)];
+            processor.process_sample(&mut samples);
+            if i >= fft_size * 2 {
+                max_error = max_error.max((samples[0] - input(i - fft_size) * 0.5).abs());
+            }
+        }
+        assert!(max_error < 1e-4, "max error {max_error}");
+
+        // only the frequency table is there, and it follows the sample rate. The magnitudes are the
+        // analyzer's, which is fed while it's active
+        let data = &processor.engines[processor.active].main().data[0];
+        assert!(data.spectrum_phase.iter().all(|&phase| phase == 0.0));
+        assert!(data.spectrum_inst_freq.iter().all(|&freq| freq == 0.0));
+        assert_eq!(data.spectrum_freq[1], 44100.0 / fft_size as f32);
+
+        processor.set_sample_rate(48000);
+        for _ in 0..processor.hop_size() {
+            processor.process_sample(&mut [0.0]);
+        }
+        let data = &processor.engines[processor.active].main().data[0];
+        assert_eq!(data.spectrum_freq[1], 48000.0 / fft_size as f32);
+
+        // and without the analyzer there's no dB conversion at all
+        let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, ComplexHalfGain);
+        processor.set_analyzer_active(false);
+        processor.set_transient_switching(true);
+        for i in 0..(fft_size * 6) {
+            processor.process_block(&mut [&mut [input(i)]]);
+        }
+        for stft in processor.engines[processor.active].bands() {
+            assert!(stft.data[0].spectrum_mag.iter().all(|&mag| mag == 0.0));
+            assert!(stft.data[0].analyzer_input_db.iter().all(|&db| db == -120.0));
+        }
+    }
+
+    #[test]
+    fn sample_rate_change_test() {
+        let fft_size = 1024;
+        for sample_rate in [44100, 48000, 88200, 96000, 192000] {
+            // set up at 44.1 kHz like the plugins are, before the host tells the real rate
+            let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, SampleRateProbe::default());
+            processor.change_resolution(Resolution::Triple);
+            processor.set_sample_rate(sample_rate);
+            assert_eq!(processor.fft_effect.sample_rate, sample_rate);
+            assert_eq!(processor.fft_effect.hop_size, processor.hop_size());
+
+            // one sine in each band, the crossovers have to follow the sample rate for them to add up
+            let input = |i: usize| {
+                [100.0, 1000.0, 9000.0]
+                    .iter()
+                    .map(|freq| (std::f32::consts::TAU * freq * i as f32 / sample_rate as f32).sin() * 0.2)
+                    .sum::<f32>()
+            };
+            let latency = processor.latency_samples();
+            let mut max_error = 0f32;
+            for i in 0..(latency * 6) {
+                let mut samples = [input(i)];
+                processor.process_sample(&mut samples);
+                if i >= latency * 2 {
+                    max_error = max_error.max((samples[0] - input(i - latency)).abs());
+                }
+            }
+            assert!(max_error < 0.01, "{sample_rate} Hz: max error {max_error}");
+
+            // the bin frequencies and the phase advance are in Hz at the current rate
+            let peak_freq = processor.fft_effect.peak_freq;
+            assert!([100.0, 1000.0, 9000.0].iter().any(|freq| (peak_freq - freq).abs() < 1.0), "{sample_rate} Hz: peak at {peak_freq} Hz");
+            let data = &processor.engines[processor.active].main().data[0];
+            let transform_size = (data.spectrum_freq.len() - 1) * 2;
+            assert_eq!(data.spectrum_freq[1], sample_rate as f32 / transform_size as f32);
+        }
+    }
+
+    #[test]
+    fn automation_is_independent_of_block_size_test() {
+        // parameter changes at fixed sample positions, the host's blocks are split there like
+        // nih_plug does with `SAMPLE_ACCURATE_AUTOMATION`
+        let events = [3000, 7000, 12000];
+        let apply = |processor: &mut FFTProcessor<SmoothedGain>, event: usize| match event {
+            3000 => {
+                processor.fft_effect.gain.set_target(0.25);
+                processor.set_mix(0.5);
+            }
+            7000 => processor.crossfade_to_resolution(2048, 4, 1, Resolution::Dual),
+            _ => processor.set_transient_switching(true),
+        };
+        let input = |i: usize, channel: usize| (i as f32 * 0.02 * (channel + 1) as f32).sin() * 0.5;
+        let num_samples = 20000;
+
+        let mut outputs = Vec::new();
+        for block_size in [1, 32, 333, 1024, 4096] {
+            let effect = SmoothedGain {
+                gain: HopSmoother::new(1.0),
+                band: 0,
+            };
+            let mut processor = FFTProcessor::new(44100, 2, 1024, 4, effect);
+            processor.fft_effect.gain.set_target(1.0);
+
+            let mut left: Vec<f32> = (0..num_samples).map(|i| input(i, 0)).collect();
+            let mut right: Vec<f32> = (0..num_samples).map(|i| input(i, 1)).collect();
+            let mut start = 0;
+            while start < num_samples {
+                if events.contains(&start) {
+                    apply(&mut processor, start);
+                }
+                let next_event = events.iter().copied().find(|&event| event > start).unwrap_or(num_samples);
+                let end = (start + block_size).min(next_event);
+                processor.process_block(&mut [&mut left[start..end], &mut right[start..end]]);
+                start = end;
+            }
+            outputs.push((block_size, left, right));
+        }
+
+        let (_, expected_left, expected_right) = &outputs[0];
+        for (block_size, left, right) in &outputs[1..] {
+            let mut max_error = 0f32;
+            for i in 0..num_samples {
+                max_error = max_error.max((left[i] - expected_left[i]).abs());
+                max_error = max_error.max((right[i] - expected_right[i]).abs());
+            }
+            assert!(max_error < 1e-5, "{block_size} samples per block: max error {max_error}");
+        }
+    }
+
+    #[test]
+    fn reset_clears_everything_test() {
+        let fft_size = 1024;
+        let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+        let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, Passthrough);
+        processor.set_transient_switching(true);
+        for i in 0..(fft_size * 4) {
+            processor.process_sample(&mut [input(i)]);
+        }
+        // in the middle of a crossfade with another one queued up
+        processor.crossfade_to(2048, 4, 1);
+        processor.crossfade_to_resolution(2048, 8, 1, Resolution::Dual);
+        for i in 0..100 {
+            processor.process_sample(&mut [input(i)]);
+        }
+
+        processor.reset();
+        assert!(!processor.is_crossfading());
+        assert_eq!((processor.fft_size(), processor.overlap()), (2048, 8));
+        assert_eq!(processor.resolution(), Resolution::Dual);
+        assert!(processor.transient_switching());
+
+        // only silence comes out of silence
+        let mut silence = vec![0f32; fft_size * 8];
+        processor.process_block(&mut [&mut silence]);
+        assert!(silence.iter().all(|&sample| sample == 0.0));
+    }
+
+    #[test]
+    fn bypass_fades_to_latency_compensated_dry_signal_test() {
+        let fft_size = 1024;
+        let input = |i: usize| (i as f32 * 0.05).sin() * 0.5;
+        let fade_length = (BYPASS_FADE_MS * 0.001 * 44100.0).round() as usize;
+        // with a muted effect the dry signal is all that can be heard
+        let mut processor = FFTProcessor::new(44100, 1, fft_size, 4, Mute);
+        let mut output: Vec<f32> = (0..(fft_size * 8)).map(input).collect();
+        let bypass_start = fft_size * 2;
+        processor.process_block(&mut [&mut output[..bypass_start]]);
+        processor.set_bypass(true);
+        processor.process_block(&mut [&mut output[bypass_start..]]);
+        assert!(processor.is_bypassed());
+
+        assert!(output[..bypass_start].iter().all(|&sample| sample.abs() < 1e-6));
+        let mut max_error = 0f32;
+        for (i, &sample) in output.iter().enumerate().skip(bypass_start) {
+            let dry = input(i - fft_size);
+            let faded = ((i - bypass_start + 1) as f32 / fade_length as f32).min(1.0);
+            max_error = max_error.max((sample - dry * faded).abs());
+        }
+        assert!(max_error < 1e-4, "max error {max_error}");
+
+        // everything kept running, back to the effect without a jump
+        processor.set_bypass(false);
+        let mut output = vec![0f32; fade_length * 2];
+        processor.process_block(&mut [&mut output]);
+        assert!(!processor.is_bypassed());
+        assert!(output[fade_length..].iter().all(|&sample| sample.abs() < 1e-6));
+    }
+}
@@ -0,0 +1,64 @@
+use nih_plug::prelude::*;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FFTSize {
+    _128 = 128,
+    _256 = 256,
+    _512 = 512,
+    _1024 = 1024,
+    _2048 = 2048,
+    _4096 = 4096,
+    _8192 = 8192,
+    _16384 = 16384,
+    _32768 = 32768,
+}
+
+impl Enum for FFTSize {
+    fn variants() -> &'static [&'static str] {
+        &["128", "256", "512", "1024", "2048", "4096", "8192", "16384", "32768"]
+    }
+
+    fn ids() -> Option<&'static [&'static str]> {
+        Some(&["128", "256", "512", "1024", "2048", "4096", "8192", "16384", "32768"])
+    }
+
+    fn to_index(self) -> usize {
+        match self {
+            FFTSize::_128 => 0,
+            FFTSize::_256 => 1,
+            FFTSize::_512 => 2,
+            FFTSize::_1024 => 3,
+            FFTSize::_2048 => 4,
+            FFTSize::_4096 => 5,
+            FFTSize::_8192 => 6,
+            FFTSize::_16384 => 7,
+            FFTSize::_32768 => 8,
+        }
+    }
+
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => FFTSize::_128,
+            1 => FFTSize::_256,
+            2 => FFTSize::_512,
+            3 => FFTSize::_1024,
+            4 => FFTSize::_2048,
+            5 => FFTSize::_4096,
+            6 => FFTSize::_8192,
+            7 => FFTSize::_16384,
+            8 => FFTSize::_32768,
+            _ => panic!("Invalid index!"),
+        }
+    }
+}
+
+impl FFTSize {
+    /// The largest size, anything that holds per-bin data should be able to fit this many bins.
+    pub const MAX: FFTSize = FFTSize::_32768;
+    pub const MIN: FFTSize = FFTSize::_128;
+
+    #[inline]
+    pub fn num_bins(&self) -> usize {
+        *self as usize / 2 + 1
+    }
+}
@@ -0,0 +1,64 @@
+use std::num::NonZeroU32;
+
+use nih_plug::prelude::*;
+
+/// Layouts for plugins without a sidechain. Stereo comes first so that it stays the default.
+pub const MAIN_LAYOUTS: &[AudioIOLayout] = &[
+    layout(2, 2, &[], "Stereo"),
+    layout(1, 1, &[], "Mono"),
+    layout(1, 2, &[], "Mono to Stereo"),
+    layout(6, 6, &[], "5.1"),
+    layout(8, 8, &[], "7.1"),
+];
+
+/// Same as `MAIN_LAYOUTS` with a sidechain input that has either as many channels as the main
+/// output or is mono.
+pub const SIDECHAIN_LAYOUTS: &[AudioIOLayout] = &[
+    layout(2, 2, &[new_nonzero_u32(2)], "Stereo"),
+    layout(2, 2, &[new_nonzero_u32(1)], "Stereo with Mono Sidechain"),
+    layout(1, 1, &[new_nonzero_u32(1)], "Mono"),
+    layout(1, 2, &[new_nonzero_u32(1)], "Mono to Stereo"),
+    layout(1, 2, &[new_nonzero_u32(2)], "Mono to Stereo with Stereo Sidechain"),
+    layout(6, 6, &[new_nonzero_u32(6)], "5.1"),
+    layout(6, 6, &[new_nonzero_u32(1)], "5.1 with Mono Sidechain"),
+    layout(8, 8, &[new_nonzero_u32(8)], "7.1"),
+    layout(8, 8, &[new_nonzero_u32(1)], "7.1 with Mono Sidechain"),
+];
+
+const fn layout(
+    input_channels: u32,
+    output_channels: u32,
+    aux_input_ports: &'static [NonZeroU32],
+    name: &'static str,
+) -> AudioIOLayout {
+    AudioIOLayout {
+        main_input_channels: NonZeroU32::new(input_channels),
+        main_output_channels: NonZeroU32::new(output_channels),
+        aux_input_ports,
+        names: PortNames {
+            layout: Some(name),
+            ..PortNames::const_default()
+        },
+        ..AudioIOLayout::const_default()
+    }
+}
+
+/// Number of channels the processor needs for `layout`, and how many of them the main input
+/// actually fills. With a mono input and a stereo output the first channel should be copied to the
+/// second one before processing.
+pub fn channel_counts(layout: &AudioIOLayout) -> (usize, usize) {
+    let output_channels = layout.main_output_channels.map_or(2, NonZeroU32::get) as usize;
+    let input_channels = layout.main_input_channels.map_or(output_channels as u32, NonZeroU32::get) as usize;
+
+    (output_channels, input_channels)
+}
+
+/// Copies the first channel to every channel from `input_channels` on, so a mono input fills all
+/// channels of a wider output.
+pub fn upmix(channels: &mut [&mut [f32]], input_channels: usize) {
+    if let Some((first, rest)) = channels.split_first_mut() {
+        for channel in rest.iter_mut().skip(input_channels.saturating_sub(1)) {
+            channel.copy_from_slice(first);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+pub mod analyzer;
+pub mod analyzer_axes;
+pub mod channel_mode;
+#[cfg(feature = "editor")]
+pub mod editor;
+pub mod fft_data;
+pub mod fft_plans;
+pub mod fft_processor;
+pub mod fft_size;
+pub mod layouts;
+pub mod note;
+pub mod overlap;
+pub mod resolution;
+pub mod smoothing;
+pub mod spectral_effect;
+pub mod spectrogram;
+pub mod transient;
+pub mod utils;
+pub mod window;
+pub mod zero_padding;
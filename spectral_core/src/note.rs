@@ -0,0 +1,66 @@
+use std::fmt;
+
+pub const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// An equal tempered note at A4 = 440 Hz and how far a frequency is off from it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    pub name: &'static str,
+    pub octave: usize,
+    pub cents: f32,
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{} {:+} ct", self.name, self.octave, self.cents.round() as i32)
+    }
+}
+
+/// The closest note to `freq`, `None` when that's below C0.
+pub fn nearest_note(freq: f32) -> Option<Note> {
+    if !freq.is_finite() || freq <= 0.0 {
+        return None;
+    }
+
+    // MIDI note numbers, C0 is 12
+    let midi = 69.0 + 12.0 * (freq / 440.0).log2();
+    let nearest = midi.round();
+    if nearest < 12.0 {
+        return None;
+    }
+    let nearest_idx = nearest as usize;
+    Some(Note {
+        name: NOTE_NAMES[nearest_idx % 12],
+        octave: nearest_idx / 12 - 1,
+        cents: (midi - nearest) * 100.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_note_test() {
+        let a4 = nearest_note(440.0).unwrap();
+        assert_eq!((a4.name, a4.octave), ("A", 4));
+        assert!(a4.cents.abs() < 1e-3);
+
+        // a bit more than a semitone above A4, the cents are off the nearest note
+        let note = nearest_note(440.0 * 2f32.powf(1.2 / 12.0)).unwrap();
+        assert_eq!((note.name, note.octave), ("A#", 4));
+        assert!((note.cents - 20.0).abs() < 0.5, "cents {}", note.cents);
+        assert_eq!(note.to_string(), "A#4 +20 ct");
+
+        assert_eq!(nearest_note(1000.0).unwrap().to_string(), "B5 +21 ct");
+
+        assert_eq!(nearest_note(0.0), None);
+        assert_eq!(nearest_note(12.0), None);
+
+        // past the top of the piano, the octaves keep going
+        let note = nearest_note(10_000.0).unwrap();
+        assert_eq!((note.name, note.octave), ("D#", 9));
+        assert!((note.cents - 7.6).abs() < 0.1, "cents {}", note.cents);
+        assert_eq!(nearest_note(20_000.0).unwrap().to_string(), "D#10 +8 ct");
+    }
+}
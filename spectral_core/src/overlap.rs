@@ -0,0 +1,39 @@
+use nih_plug::prelude::*;
+
+/// How many analysis frames overlap each output sample. The hop size is `fft_size / overlap`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Overlap {
+    _2 = 2,
+    _4 = 4,
+    _8 = 8,
+    _16 = 16,
+}
+
+impl Enum for Overlap {
+    fn variants() -> &'static [&'static str] {
+        &["2x", "4x", "8x", "16x"]
+    }
+
+    fn ids() -> Option<&'static [&'static str]> {
+        Some(&["2", "4", "8", "16"])
+    }
+
+    fn to_index(self) -> usize {
+        match self {
+            Overlap::_2 => 0,
+            Overlap::_4 => 1,
+            Overlap::_8 => 2,
+            Overlap::_16 => 3,
+        }
+    }
+
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => Overlap::_2,
+            1 => Overlap::_4,
+            2 => Overlap::_8,
+            3 => Overlap::_16,
+            _ => panic!("Invalid index!"),
+        }
+    }
+}
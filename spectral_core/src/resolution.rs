@@ -0,0 +1,109 @@
+use std::f32::consts::PI;
+
+use nih_plug::prelude::Enum;
+
+use crate::fft_size::FFTSize;
+
+/// Most bands a processor runs at once, the three of `Resolution::Triple` plus the transient band,
+/// see `FFTProcessor::set_transient_switching`.
+pub const MAX_BANDS: usize = 4;
+/// Most bands a `SpectralEffect` keeps state for, while the processor crossfades the outgoing
+/// engine's bands come after the `MAX_BANDS` of the incoming one.
+pub const MAX_EFFECT_BANDS: usize = 2 * MAX_BANDS;
+
+pub const DEFAULT_LOW_CROSSOVER_HZ: f32 = 300.0;
+pub const DEFAULT_HIGH_CROSSOVER_HZ: f32 = 4000.0;
+
+/// How many STFTs with different window sizes run side by side. Each of them only resynthesizes its
+/// own frequency range, so the lows can get a long window for frequency resolution while the highs
+/// get a short one for time resolution. Every band costs as much CPU as a single STFT of its size,
+/// and the latency is that of the longest window.
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    #[name = "Single"]
+    Single,
+    // the lows run at four times the fft size
+    #[name = "Low + Main"]
+    Dual,
+    // and the highs at a quarter of it
+    #[name = "Low + Main + High"]
+    Triple,
+}
+
+impl Resolution {
+    pub fn num_bands(self) -> usize {
+        match self {
+            Resolution::Single => 1,
+            Resolution::Dual => 2,
+            Resolution::Triple => 3,
+        }
+    }
+
+    /// Window size of `band` for a main FFT size of `fft_size`. Band 0 is always the main band, 1
+    /// the lows and 2 the highs.
+    pub fn band_fft_size(fft_size: usize, band: usize) -> usize {
+        match band {
+            0 => fft_size,
+            1 => (fft_size * 4).min(FFTSize::MAX as usize),
+            _ => (fft_size / 4).max(FFTSize::MIN as usize),
+        }
+    }
+
+    /// Latency of all bands together, which is the window size of the longest one.
+    pub fn latency(self, fft_size: usize) -> usize {
+        (0..self.num_bands())
+            .map(|band| Self::band_fft_size(fft_size, band))
+            .max()
+            .unwrap_or(fft_size)
+    }
+
+    /// Gain of `band` at `freq`, with `crossovers` being the low and high crossover frequency.
+    /// Neighbouring bands cross over with complementary slopes, so the gains of all bands add up to
+    /// one at every frequency.
+    pub fn band_gain(self, band: usize, freq: f32, crossovers: (f32, f32)) -> f32 {
+        let below_low = lowpass_gain(freq, crossovers.0);
+        let below_high = lowpass_gain(freq, crossovers.1);
+        match (self, band) {
+            (Resolution::Single, _) => 1.0,
+            (_, 1) => below_low,
+            (Resolution::Dual, _) => 1.0 - below_low,
+            (Resolution::Triple, 0) => below_high - below_low,
+            _ => 1.0 - below_high,
+        }
+    }
+}
+
+/// Transform and hop size of one band, see `SpectralEffect::set_bands`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BandSize {
+    pub transform_size: usize,
+    pub hop_size: usize,
+}
+
+/// Raised cosine slope from one to zero over the octave centered on `crossover`, in log frequency.
+fn lowpass_gain(freq: f32, crossover: f32) -> f32 {
+    let position = ((freq / crossover).log2() + 0.5).clamp(0.0, 1.0);
+    0.5 + 0.5 * (PI * position).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn band_gains_add_up_test() {
+        let crossovers = (DEFAULT_LOW_CROSSOVER_HZ, DEFAULT_HIGH_CROSSOVER_HZ);
+        for resolution in [Resolution::Single, Resolution::Dual, Resolution::Triple] {
+            for freq in [0.0, 50.0, 212.0, 300.0, 424.0, 1000.0, 4000.0, 20000.0] {
+                let sum: f32 = (0..resolution.num_bands())
+                    .map(|band| resolution.band_gain(band, freq, crossovers))
+                    .sum();
+                assert!((sum - 1.0).abs() < 1e-6, "{resolution:?} at {freq} Hz: {sum}");
+            }
+        }
+
+        // an octave around the crossover is all the overlap there is
+        assert_eq!(Resolution::Dual.band_gain(1, 200.0, crossovers), 1.0);
+        assert_eq!(Resolution::Dual.band_gain(1, 450.0, crossovers), 0.0);
+    }
+}
@@ -0,0 +1,96 @@
+use nih_plug::prelude::{Smoother, SmoothingStyle};
+
+/// How long a parameter change takes to ramp in, long enough to cover a few large host blocks.
+pub const HOP_SMOOTHING_MS: f32 = 50.0;
+
+/// A parameter that moves to a new value over a few hops instead of jumping at the next one. The
+/// effects only look at their parameters once per hop, so it's advanced once per hop of the main
+/// band, the other bands use the main band's current value.
+pub struct HopSmoother {
+    smoother: Smoother<f32>,
+    // `None` until the first target is set, which is jumped to since there's nothing to ramp from
+    target: Option<f32>,
+    hop_rate: f32,
+}
+
+impl HopSmoother {
+    pub fn new(value: f32) -> Self {
+        let smoother = Smoother::new(SmoothingStyle::Linear(HOP_SMOOTHING_MS));
+        smoother.reset(value);
+
+        Self {
+            smoother,
+            target: None,
+            hop_rate: 44100.0 / 256.0,
+        }
+    }
+
+    /// Takes the values `SpectralEffect::set_sample_rate` is called with.
+    pub fn set_hop_rate(&mut self, sample_rate: usize, hop_size: usize) {
+        self.hop_rate = sample_rate as f32 / hop_size.max(1) as f32;
+    }
+
+    /// Starts ramping towards `target`. Setting the same target again, as happens on every block,
+    /// doesn't restart the ramp.
+    pub fn set_target(&mut self, target: f32) {
+        match self.target {
+            Some(current) if current == target => (),
+            Some(_) => self.smoother.set_target(self.hop_rate, target),
+            None => self.smoother.reset(target),
+        }
+        self.target = Some(target);
+    }
+
+    /// Jumps straight to the target, for when there's nothing to ramp from.
+    pub fn reset(&mut self) {
+        if let Some(target) = self.target {
+            self.smoother.reset(target);
+        }
+    }
+
+    /// Moves one hop further towards the target and returns the new value.
+    pub fn next_hop(&mut self) -> f32 {
+        self.smoother.next()
+    }
+
+    pub fn value(&self) -> f32 {
+        self.smoother.previous_value()
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.smoother.is_smoothing()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramps_over_the_smoothing_time_test() {
+        let mut smoother = HopSmoother::new(0.0);
+        // 100 hops per second, so the ramp takes 5 hops
+        smoother.set_hop_rate(44100, 441);
+        // the first target is taken as it is
+        smoother.set_target(0.0);
+        assert_eq!(smoother.next_hop(), 0.0);
+
+        smoother.set_target(10.0);
+        let values: Vec<f32> = (0..6)
+            .map(|_| {
+                // the host sets the same target again on every block
+                smoother.set_target(10.0);
+                smoother.next_hop()
+            })
+            .collect();
+        assert!(values[..4].windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(values[0] > 0.0 && values[3] < 10.0);
+        assert_eq!(values[4], 10.0);
+        assert_eq!(values[5], 10.0);
+        assert!(!smoother.is_smoothing());
+
+        smoother.set_target(-10.0);
+        smoother.reset();
+        assert_eq!(smoother.value(), -10.0);
+    }
+}
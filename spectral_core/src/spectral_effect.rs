@@ -0,0 +1,104 @@
+use realfft::num_complex::Complex;
+
+use crate::{fft_data::FFTData, resolution::BandSize};
+
+/// A spectral effect driven by the `FFTProcessor`.
+///
+/// Once per hop the processor transforms every channel into `fft_out` of its `FFTData`, fills the
+/// values asked for by `spectrum_values` and hands them to `process_spectrum`. `spectrum_freq` is a
+/// table that's only recalculated when the size or the sample rate changes. Whatever the effect
+/// writes into `output` is what gets resynthesized. `input`, `aux` and `output` all have one entry
+/// per channel, and there can be anywhere from one to `MAX_CHANNELS` of them. In one of the
+/// mid/side `ChannelMode`s the two channels are mid and side instead of left and right.
+pub trait SpectralEffect {
+    /// Called when the processor is created and whenever its number of channels changes, never
+    /// from the audio thread. Per-channel state should be allocated here.
+    fn set_num_channels(&mut self, _num_channels: usize) {}
+
+    /// Called whenever the FFT size changes. With zero padding `fft_size` is the padded transform
+    /// size, which is what the bins are spaced by. Per-bin state should be resized here. While the
+    /// processor crossfades between two sizes this gets the larger of them and `process_spectrum`
+    /// is called with spectra of both sizes, see `crossfade_started`. With more than one band it's
+    /// the size of the largest band, see `set_bands`.
+    fn resize(&mut self, _fft_size: usize) {}
+
+    /// Called whenever the sample rate or the hop size changes. With more than one band this is the
+    /// main band's hop size.
+    fn set_sample_rate(&mut self, _sample_rate: usize, _hop_size: usize) {}
+
+    /// Called after `resize` with the sizes of every band of the processor's `Resolution`, followed
+    /// by the transient band if transient switching is on. With more than one band
+    /// `process_spectrum` is called for each of them at its own hop rate, right after `set_band`
+    /// with the band's index. The spectra only need to be right within the band's frequency range,
+    /// but per-bin state that is kept from hop to hop has to be kept per band. While crossfading
+    /// the outgoing engine's bands follow from `MAX_BANDS` on, the entries in between are zero.
+    fn set_bands(&mut self, _bands: &[BandSize]) {}
+
+    /// Called before every `process_spectrum` with the index of the band the spectra belong to,
+    /// below `MAX_EFFECT_BANDS`. Band 0 is the main band of the engine that's heard once a crossfade
+    /// is done, hop-rate parameter smoothing should only step there.
+    fn set_band(&mut self, _band: usize) {}
+
+    /// Called when the processor starts crossfading to new sizes, before `resize` and `set_bands`.
+    /// The engine that ran so far keeps running until it's faded out, with its bands passed to
+    /// `set_band` as `MAX_BANDS + band`, while the new engine takes over the bands from 0. Per-band
+    /// state has to move along from `band` to `MAX_BANDS + band`, and the new engine's bands start
+    /// over.
+    fn crossfade_started(&mut self) {}
+
+    /// Called from `FFTProcessor::reset`, e.g. after a transport jump. Envelopes, held frames and
+    /// anything else that's kept from hop to hop should be cleared, without allocating.
+    fn reset(&mut self) {}
+
+    /// Which values besides `fft_out` and `spectrum_freq` `process_spectrum` reads, of both the main
+    /// and the aux input. The others are left as they were, so an effect that only applies a gain
+    /// to every bin can multiply `fft_out` and skip the polar conversion altogether.
+    fn spectrum_values(&self) -> SpectrumValues {
+        SpectrumValues::ALL
+    }
+
+    /// `aux` is only `Some` if the processor was created with `new_with_aux`.
+    fn process_spectrum(
+        &mut self,
+        input: &[FFTData],
+        aux: Option<&[FFTData]>,
+        output: &mut [Vec<Complex<f32>>],
+    );
+
+    /// Called right after `process_spectrum`. `spectrum_mag` and `spectrum_db` of `output` now hold
+    /// the processed bins and `analyzer_input_db` the unprocessed ones, of `aux` as well, which makes
+    /// this the place to publish analyzer data. Only the main band is analyzed, over its whole
+    /// frequency range.
+    fn analyze(&mut self, _output: &[FFTData], _aux: Option<&[FFTData]>, _fft_size: usize) {}
+}
+
+/// Per-bin values the processor derives from `fft_out` before `process_spectrum`, each of them
+/// costs a transcendental function per bin and channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectrumValues {
+    /// `spectrum_mag`
+    pub magnitude: bool,
+    /// `spectrum_db`, which needs the magnitude as well
+    pub db: bool,
+    /// `spectrum_phase`
+    pub phase: bool,
+    /// `spectrum_phase_advance` and `spectrum_inst_freq`, which need the phase as well
+    pub phase_tracking: bool,
+}
+
+impl SpectrumValues {
+    pub const ALL: SpectrumValues = SpectrumValues {
+        magnitude: true,
+        db: true,
+        phase: true,
+        phase_tracking: true,
+    };
+
+    /// Just `fft_out`, for effects that work on the complex bins.
+    pub const NONE: SpectrumValues = SpectrumValues {
+        magnitude: false,
+        db: false,
+        phase: false,
+        phase_tracking: false,
+    };
+}
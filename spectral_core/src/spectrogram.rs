@@ -0,0 +1,167 @@
+use nih_plug::prelude::Enum;
+
+use crate::{analyzer::FLOOR_DB, utils};
+
+pub const DEFAULT_MIN_DB: f32 = -100.0;
+pub const DEFAULT_MAX_DB: f32 = 0.0;
+pub const MIN_FREQ_HZ: f32 = 20.0;
+
+/// Maps the spectrogram's dB range to colors.
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum Colormap {
+    Magma,
+    Inferno,
+    Viridis,
+    Grayscale,
+}
+
+// evenly spaced stops, sampled from matplotlib's colormaps
+const MAGMA: [[u8; 3]; 5] = [[0, 0, 4], [81, 18, 124], [183, 55, 121], [252, 137, 97], [252, 253, 191]];
+const INFERNO: [[u8; 3]; 5] = [[0, 0, 4], [87, 16, 110], [188, 55, 84], [249, 142, 9], [252, 255, 164]];
+const VIRIDIS: [[u8; 3]; 5] = [[68, 1, 84], [59, 82, 139], [33, 145, 140], [94, 201, 98], [253, 231, 37]];
+const GRAYSCALE: [[u8; 3]; 2] = [[0, 0, 0], [255, 255, 255]];
+
+impl Colormap {
+    /// The color for a `[0, 1]` value, 0 being the bottom of the dB range.
+    pub fn color(self, value: f32) -> [u8; 3] {
+        let stops: &[[u8; 3]] = match self {
+            Colormap::Magma => &MAGMA,
+            Colormap::Inferno => &INFERNO,
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Grayscale => &GRAYSCALE,
+        };
+        let position = value.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let idx = (position as usize).min(stops.len() - 2);
+        let t = position - idx as f32;
+        let (a, b) = (stops[idx], stops[idx + 1]);
+        [0, 1, 2].map(|c| utils::lerp(a[c] as f32, b[c] as f32, t).round() as u8)
+    }
+}
+
+/// The last `num_columns` analyzer frames, each one resampled to `num_rows` log-spaced frequency
+/// rows so the editor can draw them as a scrolling image.
+pub struct SpectrogramBuffer {
+    num_columns: usize,
+    num_rows: usize,
+    min_hz: f32,
+    max_hz: f32,
+    // column after column, `num_rows` dB values each, lowest frequency first
+    columns: Vec<f32>,
+    // where the next frame goes, which is the oldest one
+    next_column: usize,
+}
+
+impl SpectrogramBuffer {
+    pub fn new(num_columns: usize, num_rows: usize) -> Self {
+        Self {
+            num_columns,
+            num_rows,
+            min_hz: MIN_FREQ_HZ,
+            max_hz: 22_050.0,
+            columns: vec![FLOOR_DB; num_columns * num_rows],
+            next_column: 0,
+        }
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.num_columns
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// The history is dropped when the range changes, its rows no longer line up.
+    pub fn set_frequency_range(&mut self, min_hz: f32, max_hz: f32) {
+        if min_hz != self.min_hz || max_hz != self.max_hz {
+            self.min_hz = min_hz;
+            self.max_hz = max_hz;
+            self.clear();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.columns.fill(FLOOR_DB);
+        self.next_column = 0;
+    }
+
+    /// Adds a frame of `AnalyzerData` magnitudes in dB, `bin_hz` apart. A row spanning several bins
+    /// shows the loudest one, rows narrower than a bin are interpolated.
+    pub fn push(&mut self, magnitudes: &[f32], bin_hz: f32) {
+        if magnitudes.is_empty() {
+            return;
+        }
+        let last_bin = (magnitudes.len() - 1) as f32;
+        let ratio = (self.max_hz / self.min_hz).ln();
+        let row_bin = |row: f32| (self.min_hz * (ratio * row / self.num_rows as f32).exp() / bin_hz).min(last_bin);
+
+        let column = &mut self.columns[self.next_column * self.num_rows..(self.next_column + 1) * self.num_rows];
+        for (row, db) in column.iter_mut().enumerate() {
+            let (start, end) = (row_bin(row as f32), row_bin(row as f32 + 1.0));
+            *db = if end - start < 1.0 {
+                let center = (start + end) * 0.5;
+                let idx = (center as usize).min(magnitudes.len() - 1);
+                let next = (idx + 1).min(magnitudes.len() - 1);
+                utils::lerp(magnitudes[idx], magnitudes[next], center - idx as f32)
+            } else {
+                magnitudes[start as usize..=end as usize].iter().fold(FLOOR_DB, |max, &db| max.max(db))
+            };
+        }
+        self.next_column = (self.next_column + 1) % self.num_columns;
+    }
+
+    /// Writes the history as an RGBA image of `num_columns` by `num_rows` pixels, the newest frame
+    /// on the right and the highest frequency at the top.
+    pub fn render(&self, colormap: Colormap, min_db: f32, max_db: f32, rgba: &mut [u8]) {
+        let range = (max_db - min_db).max(1.0);
+        for (y, pixels) in rgba.chunks_exact_mut(self.num_columns * 4).take(self.num_rows).enumerate() {
+            let row = self.num_rows - 1 - y;
+            for (x, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+                let column = (self.next_column + x) % self.num_columns;
+                let db = self.columns[column * self.num_rows + row];
+                let [r, g, b] = colormap.color((db - min_db) / range);
+                pixel.copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colormap_test() {
+        assert_eq!(Colormap::Magma.color(0.0), MAGMA[0]);
+        assert_eq!(Colormap::Viridis.color(1.0), VIRIDIS[4]);
+        assert_eq!(Colormap::Grayscale.color(0.5), [128, 128, 128]);
+        // out of range values stay at the ends
+        assert_eq!(Colormap::Inferno.color(2.0), INFERNO[4]);
+    }
+
+    #[test]
+    fn scrolls_with_high_frequencies_on_top_test() {
+        let (columns, rows) = (4, 8);
+        let mut buffer = SpectrogramBuffer::new(columns, rows);
+        buffer.set_frequency_range(20.0, 20_000.0);
+
+        // 1025 bins of a 2048 point FFT at 40.96 kHz, loud above 5 kHz
+        let bin_hz = 20.0;
+        let magnitudes: Vec<f32> = (0..1025).map(|bin| if bin as f32 * bin_hz > 5_000.0 { 0.0 } else { FLOOR_DB }).collect();
+        buffer.push(&magnitudes, bin_hz);
+
+        let mut rgba = vec![0u8; columns * rows * 4];
+        buffer.render(Colormap::Grayscale, -100.0, 0.0, &mut rgba);
+        let pixel = |x: usize, y: usize| rgba[(y * columns + x) * 4];
+        // the newest frame is the rightmost column
+        assert_eq!(pixel(columns - 1, 0), 255);
+        assert_eq!(pixel(columns - 1, rows - 1), 0);
+        assert_eq!(pixel(0, 0), 0);
+
+        buffer.push(&vec![FLOOR_DB; 1025], bin_hz);
+        buffer.render(Colormap::Grayscale, -100.0, 0.0, &mut rgba);
+        let pixel = |x: usize, y: usize| rgba[(y * columns + x) * 4];
+        assert_eq!(pixel(columns - 2, 0), 255);
+        assert_eq!(pixel(columns - 1, 0), 0);
+    }
+}
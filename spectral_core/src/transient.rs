@@ -0,0 +1,161 @@
+use crate::{fft_size::FFTSize, utils};
+
+pub const DEFAULT_THRESHOLD_DB: f32 = 9.0;
+
+const FAST_MS: f32 = 1.0;
+const SLOW_MS: f32 = 50.0;
+// onsets quieter than -60 dB are ignored
+const MIN_LEVEL: f32 = 0.001;
+
+/// Window size of the short band that takes over around transients, an eighth of the main one.
+pub fn transient_fft_size(fft_size: usize) -> usize {
+    (fft_size / 8).max(FFTSize::MIN as usize)
+}
+
+/// Detects onsets as the moment a fast envelope follower rises more than the threshold above a slow
+/// one. Fires once per onset, the fast envelope has to drop back to the slow one before the next.
+pub struct TransientDetector {
+    fast: f32,
+    slow: f32,
+    fast_coeff: f32,
+    slow_coeff: f32,
+    // how far the fast envelope has to rise above the slow one, as a gain ratio
+    threshold: f32,
+    armed: bool,
+}
+
+impl TransientDetector {
+    pub fn new(sample_rate: usize) -> Self {
+        let mut detector = Self {
+            fast: 0.0,
+            slow: 0.0,
+            fast_coeff: 0.0,
+            slow_coeff: 0.0,
+            threshold: utils::db_to_gain(DEFAULT_THRESHOLD_DB),
+            armed: true,
+        };
+        detector.set_sample_rate(sample_rate);
+
+        detector
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: usize) {
+        self.fast_coeff = (-1.0 / (FAST_MS * sample_rate as f32 * 0.001)).exp();
+        self.slow_coeff = (-1.0 / (SLOW_MS * sample_rate as f32 * 0.001)).exp();
+    }
+
+    pub fn set_threshold(&mut self, threshold_db: f32) {
+        self.threshold = utils::db_to_gain(threshold_db);
+    }
+
+    pub fn reset(&mut self) {
+        self.fast = 0.0;
+        self.slow = 0.0;
+        self.armed = true;
+    }
+
+    /// Feeds the absolute level of the next sample, returns whether an onset starts there.
+    pub fn process(&mut self, level: f32) -> bool {
+        self.fast = level + self.fast_coeff * (self.fast - level);
+        self.slow = level + self.slow_coeff * (self.slow - level);
+
+        let onset = self.armed && self.fast > MIN_LEVEL && self.fast > self.slow * self.threshold;
+        if onset {
+            self.armed = false;
+        } else if self.fast <= self.slow {
+            self.armed = true;
+        }
+
+        onset
+    }
+}
+
+/// How much of the transient band is heard instead of the regular bands. Fades in as soon as an
+/// onset is detected, holds for as long as the long windows smear it, then fades back out.
+pub struct TransientSwitch {
+    pub detector: TransientDetector,
+    gain: f32,
+    // samples until the fade back out starts
+    hold: usize,
+
+    attack_step: f32,
+    hold_length: usize,
+    release_step: f32,
+}
+
+impl TransientSwitch {
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            detector: TransientDetector::new(sample_rate),
+            gain: 0.0,
+            hold: 0,
+
+            attack_step: 1.0,
+            hold_length: 0,
+            release_step: 1.0,
+        }
+    }
+
+    /// `short_size` is the transient band's window size, `latency` the processor's. The output for
+    /// an onset's input is due `latency` samples after it's detected, and every window containing
+    /// the onset is smeared over a window size before and after it, so the switch has to start right
+    /// away and hold for twice the latency.
+    pub fn set_lengths(&mut self, short_size: usize, latency: usize) {
+        self.attack_step = 1.0 / short_size.max(1) as f32;
+        self.hold_length = 2 * latency;
+        self.release_step = 1.0 / (latency / 2).max(1) as f32;
+    }
+
+    pub fn reset(&mut self) {
+        self.detector.reset();
+        self.gain = 0.0;
+        self.hold = 0;
+    }
+
+    /// Gain of the transient band for the next sample, given its absolute level.
+    pub fn next_gain(&mut self, level: f32) -> f32 {
+        if self.detector.process(level) {
+            self.hold = self.hold_length;
+        }
+
+        if self.hold > 0 {
+            self.hold -= 1;
+            self.gain = (self.gain + self.attack_step).min(1.0);
+        } else {
+            self.gain = (self.gain - self.release_step).max(0.0);
+        }
+
+        self.gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_onsets_once_test() {
+        let mut detector = TransientDetector::new(44100);
+        let sine = |i: usize| (i as f32 * 0.05).sin().abs();
+
+        let onsets = (0..44100).filter(|&i| detector.process(if i < 10000 { 0.0 } else { sine(i) })).count();
+        assert_eq!(onsets, 1);
+
+        // a steady signal doesn't trigger anything, neither does a small step
+        let onsets = (0..44100).filter(|&i| detector.process(sine(i) * if i < 20000 { 1.0 } else { 1.5 })).count();
+        assert_eq!(onsets, 0);
+    }
+
+    #[test]
+    fn switch_fades_in_holds_and_fades_out_test() {
+        let mut switch = TransientSwitch::new(44100);
+        switch.set_lengths(256, 2048);
+
+        let gains: Vec<f32> = (0..10000).map(|i| switch.next_gain(if i < 1000 { 0.0 } else { 0.5 })).collect();
+        assert!(gains[..1000].iter().all(|&gain| gain == 0.0));
+        assert_eq!(gains[1000 + 255], 1.0);
+        assert_eq!(gains[1000 + 4095], 1.0);
+        assert!(gains[1000 + 4096] < 1.0);
+        assert_eq!(gains[1000 + 4096 + 1024], 0.0);
+    }
+}
@@ -0,0 +1,207 @@
+use std::f32::consts::TAU;
+
+use crate::fft_size::FFTSize;
+
+pub const MINUS_INF_DB: f32 = -100f32;
+pub const MINUS_INF_GAIN: f32 = 1e-5;
+
+// https://www.musicdsp.org/en/latest/Filters/257-1-pole-lpf-for-smooth-parameter-changes.html
+#[derive(Clone, Copy)]
+pub struct SimpleLPF {
+    pub a: f32,
+    b: f32,
+    z: f32,
+    pub log_scale: f32,
+    mina: f32,
+    maxa: f32,
+}
+
+impl SimpleLPF {
+    pub fn new(a: f32) -> Self {
+        Self {
+            a,
+            b: 1.0f32 - a,
+            z: 0.0f32,
+            log_scale: 3.0f32,
+            mina: 0.0f32,
+            maxa: 1.0f32,
+        }
+    }
+
+    #[inline]
+    pub fn set_a(&mut self, a: f32) {
+        self.a = a;
+        self.b = 1.0 - a;
+    }
+
+    #[inline]
+    pub fn set_a_log_scale(&mut self, index: usize, total_bins: usize) {
+        let log_position = ((index as f32).ln() / (total_bins as f32).ln()).sqrt();
+        let dynamic_a = self.mina + log_position * (self.maxa - self.mina);
+        self.set_a(dynamic_a);
+    }
+
+    pub fn calculate_a_range(&mut self) {
+        let min_a = self.a / self.log_scale;
+        let max_a = self.a * self.log_scale;
+        self.mina = min_a.clamp(0.0, 1.0);
+        self.maxa = max_a.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.z = (sample * self.b) + (self.z * self.a);
+        self.z
+    }
+
+    pub fn reset(&mut self) {
+        self.z = 0.0;
+    }
+}
+
+pub fn multiply_vectors(a: &[f32], b: &[f32]) -> Vec<f32> {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).collect::<Vec<f32>>()
+}
+
+pub fn multiply_vectors_in_place(a: &mut [f32], b: &[f32]) {
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        *x *= *y;
+    }
+}
+
+#[inline]
+pub fn gain_to_db(x: f32) -> f32 {
+    f32::max(x, MINUS_INF_GAIN).log10() * 20.0
+}
+
+#[inline]
+pub fn db_to_gain(x: f32) -> f32 {
+    if x > MINUS_INF_DB {
+        10.0f32.powf(x * 0.05f32)
+    } else {
+        0.0
+    }
+}
+
+pub fn freq_to_bin(f: f32, fft_size: usize, sample_rate: f32) -> usize {
+    ((f * fft_size as f32) / sample_rate).floor() as usize
+}
+
+#[inline]
+pub fn fft_size_to_bins(size: usize) -> usize {
+    (size / 2) + 1
+}
+
+/// `len` copies of `value` in a vec that has room for `capacity` elements, so it can later be
+/// resized up to `capacity` without reallocating.
+pub fn vec_with_capacity<T: Clone>(len: usize, capacity: usize, value: T) -> Vec<T> {
+    let mut vec = Vec::with_capacity(capacity.max(len));
+    vec.resize(len, value);
+    vec
+}
+
+/// Per-bin vec that can be resized to any `FFTSize` without reallocating, use this for anything
+/// that gets resized on the audio thread.
+pub fn bins_vec<T: Clone>(num_bins: usize, value: T) -> Vec<T> {
+    vec_with_capacity(num_bins, FFTSize::MAX.num_bins(), value)
+}
+
+/// Fills `synthesis_window` with `window` times `gain`, divided by the sum of the overlapping,
+/// squared `window`s at each output sample when frames are `hop_size` apart. Windowing before the
+/// FFT with `window` and after it with this one puts the overlap-added output back at `gain`, sample
+/// for sample, even where the squared windows don't add up to a constant.
+pub fn fill_synthesis_window(window: &[f32], hop_size: usize, gain: f32, synthesis_window: &mut [f32]) {
+    for offset in 0..hop_size.min(window.len()) {
+        let overlap_sum = window.iter().skip(offset).step_by(hop_size).map(|w| w * w).sum::<f32>();
+        for i in (offset..window.len()).step_by(hop_size) {
+            // nothing to normalize where all overlapping windows are zero
+            synthesis_window[i] = if overlap_sum > f32::EPSILON { gain * window[i] / overlap_sum } else { 0.0 };
+        }
+    }
+}
+
+#[inline]
+pub fn calculate_peakness(x: f32, p: f32, one_over_p: f32) -> f32 {
+    //x.clamp(0.0, 1.0)//.powi(2)
+    (1.0f32 - (1.0f32 - x.clamp(0.0, 1.0)).powf(p)).powf(one_over_p)
+}
+
+#[inline]
+pub fn peakiness_scaled(x: f32, p: f32, one_over_p: f32, min_x: f32, max_x: f32, min_y: f32, height: f32) -> f32 {
+    (1.0f32 - 
+        (
+            1.0f32 - ((x.clamp(min_x, max_x) - min_x) / max_x)
+        )
+        .powf(p)
+    ).powf(one_over_p) * height + min_y
+}
+
+/// Wraps `phase` to -pi..pi.
+#[inline]
+pub fn wrap_phase(phase: f32) -> f32 {
+    phase - TAU * (phase / TAU).round()
+}
+
+#[inline]
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    (1.0f32 - t) * a + t * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vector_mult_test() {
+        let expected = vec![1.0, 4.0, 6.0, 50.0];
+        let a = vec![1.0, 2.0, 3.0, 5.0];
+        let b = vec![1.0, 2.0, 2.0, 10.0];
+
+        let mult = multiply_vectors(&a, &b);
+
+        assert_eq!(expected, mult);
+    }
+
+    #[test]
+    fn multiply_vectors_in_place_test() {
+        let expected = vec![1.0, 4.0, 6.0, 50.0];
+        let mut a = vec![1.0, 2.0, 3.0, 5.0];
+        let b = vec![1.0, 2.0, 2.0, 10.0];
+
+        multiply_vectors_in_place(&mut a, &b);
+
+        assert_eq!(expected, a);
+    }
+
+    #[test]
+    fn fill_synthesis_window_test() {
+        // a blackman-harris window at 2x overlap is far from adding up to a constant
+        let window = apodize::blackman_iter(1024).map(|x| x as f32).collect::<Vec<f32>>();
+        for hop_size in [512, 256, 64] {
+            let mut synthesis_window = vec![0f32; window.len()];
+            fill_synthesis_window(&window, hop_size, 2.0, &mut synthesis_window);
+
+            for offset in 0..hop_size {
+                let sum = (offset..window.len()).step_by(hop_size).map(|i| window[i] * synthesis_window[i]).sum::<f32>();
+                assert!((sum - 2.0).abs() < 1e-4, "hop {hop_size}, offset {offset}: {sum}");
+            }
+        }
+    }
+
+    #[test]
+    fn wrap_phase_test() {
+        assert!((wrap_phase(3.0 * TAU + 0.5) - 0.5).abs() < 1e-4);
+        assert!((wrap_phase(-TAU - 0.5) + 0.5).abs() < 1e-4);
+        assert!((wrap_phase(1.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bins_vec_resize_keeps_allocation_test() {
+        let mut bins = bins_vec(fft_size_to_bins(128), 0f32);
+        let ptr = bins.as_ptr();
+
+        bins.resize(FFTSize::MAX.num_bins(), 0f32);
+
+        assert_eq!(ptr, bins.as_ptr());
+    }
+}
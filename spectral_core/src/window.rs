@@ -0,0 +1,76 @@
+use nih_plug::prelude::Enum;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum WindowType {
+    Hann,
+    Hamming,
+    Blackman,
+    #[name = "Blackman-Harris"]
+    BlackmanHarris,
+    Nuttall,
+    Kaiser,
+    // mostly useful for the analyzer, peaks keep their level no matter where they fall between bins
+    #[name = "Flat-Top"]
+    FlatTop,
+}
+
+impl WindowType {
+    /// The least overlap the processor runs this window at. Below it the overlapping squared
+    /// windows vary too much over a hop, at 2x by a factor of 4 for blackman, 11 for
+    /// blackman-harris and nuttall and 1500 for flat-top, so the per sample normalization in
+    /// `utils::fill_synthesis_window` would boost anything an effect changes near the frame edges.
+    /// Hann and hamming stay within a factor of 2.
+    pub fn min_overlap(self) -> usize {
+        match self {
+            WindowType::Hann | WindowType::Hamming => 2,
+            WindowType::Blackman
+            | WindowType::BlackmanHarris
+            | WindowType::Nuttall
+            | WindowType::Kaiser
+            | WindowType::FlatTop => 4,
+        }
+    }
+}
+
+/// Fills `window` with `window_type`. `kaiser_beta` is only used by the kaiser window.
+pub fn fill_window(window: &mut [f32], window_type: WindowType, kaiser_beta: f32) {
+    let size = window.len();
+    match window_type {
+        WindowType::Hann => fill_from_iter(window, apodize::hanning_iter(size)),
+        WindowType::Hamming => fill_from_iter(window, apodize::hamming_iter(size)),
+        WindowType::Blackman => fill_from_iter(window, apodize::cosine_iter(0.42, 0.5, 0.08, 0.0, size)),
+        // apodize's `blackman_iter` uses the 4-term blackman-harris coefficients
+        WindowType::BlackmanHarris => fill_from_iter(window, apodize::blackman_iter(size)),
+        WindowType::Nuttall => fill_from_iter(window, apodize::nuttall_iter(size)),
+        // HFT70 from Heinzel et al., the amplitude is irrelevant since the processor normalizes it
+        WindowType::FlatTop => fill_from_iter(window, apodize::cosine_iter(1.0, 1.90796, 1.07349, 0.18199, size)),
+        WindowType::Kaiser => {
+            let denominator = bessel_i0(kaiser_beta);
+            let last = (size - 1) as f32;
+            for (i, w) in window.iter_mut().enumerate() {
+                let x = 2.0 * i as f32 / last - 1.0;
+                *w = bessel_i0(kaiser_beta * (1.0 - x * x).max(0.0).sqrt()) / denominator;
+            }
+        }
+    }
+}
+
+fn fill_from_iter(window: &mut [f32], iter: impl Iterator<Item = f64>) {
+    for (w, x) in window.iter_mut().zip(iter) {
+        *w = x as f32;
+    }
+}
+
+/// Zeroth order modified bessel function of the first kind, evaluated with its power series.
+fn bessel_i0(x: f32) -> f32 {
+    let half_x = x / 2.0;
+    let mut sum = 1.0f32;
+    let mut term = 1.0f32;
+    let mut k = 1.0f32;
+    while term > sum * 1e-8 {
+        term *= (half_x / k) * (half_x / k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
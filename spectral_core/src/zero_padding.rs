@@ -0,0 +1,38 @@
+use nih_plug::prelude::*;
+
+/// How many times longer than the window the transform is, the rest is zeros. More padding
+/// interpolates the spectrum more finely without making the window any longer. The transform never
+/// gets longer than the largest `FFTSize`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ZeroPadding {
+    _1 = 1,
+    _2 = 2,
+    _4 = 4,
+}
+
+impl Enum for ZeroPadding {
+    fn variants() -> &'static [&'static str] {
+        &["1x", "2x", "4x"]
+    }
+
+    fn ids() -> Option<&'static [&'static str]> {
+        Some(&["1", "2", "4"])
+    }
+
+    fn to_index(self) -> usize {
+        match self {
+            ZeroPadding::_1 => 0,
+            ZeroPadding::_2 => 1,
+            ZeroPadding::_4 => 2,
+        }
+    }
+
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => ZeroPadding::_1,
+            1 => ZeroPadding::_2,
+            2 => ZeroPadding::_4,
+            _ => panic!("Invalid index!"),
+        }
+    }
+}